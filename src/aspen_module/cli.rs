@@ -1,56 +1,58 @@
-use std::{fs, process};
+use std::fs;
 use std::path::{Path, PathBuf};
 use colored::Colorize;
-use clap::{Arg, Command};
+use clap::{value_parser, Arg, ArgAction, Command};
 
 #[cfg(any(target_os = "macos", target_os = "linux"))]
-use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
-#[cfg(any(target_os = "macos", target_os = "linux"))]
+use std::os::unix::fs::PermissionsExt;
+#[cfg(target_os = "macos")]
+use std::os::unix::fs::OpenOptionsExt;
+#[cfg(target_os = "macos")]
 use std::fs::{OpenOptions};
-#[cfg(any(target_os = "macos", target_os = "linux"))]
+#[cfg(target_os = "macos")]
 use std::io::prelude::*;
 
-use crate::ssh_module::command::{
-    impl_servers_table_action, impl_ssh_action,
-    import_get_servers_path_action,
-    import_set_servers_path_action,
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::command::{impl_known_hosts_action, impl_path_action, impl_ssh_action};
+use crate::ssh_module::exec::{impl_exec_action, impl_exec_all_action};
+use crate::ssh_module::export::impl_export_action;
+use crate::ssh_module::import::impl_import_action;
+use crate::ssh_module::listing::impl_servers_table_action;
+use crate::ssh_module::manage::{
+    impl_add_server_action, impl_cp_server_action, impl_edit_server_action, impl_rm_server_action,
 };
+use crate::sftp_module::command::{impl_get_action, impl_put_action};
+use crate::sftp_module::sync::impl_sync_action;
+use crate::tunnel_module::command::{impl_rtunnel_action, impl_socks_action, impl_tunnel_action};
+use crate::vault_module::command::impl_vault_action;
+#[cfg(target_os = "macos")]
+use crate::ssh_module::script::{CONTROLLER_SCRIPT, EXPECT_SCRIPT};
 
-pub fn get_home_dir() -> PathBuf {
-    let home_dir = match dirs::home_dir() {
-        None => {
-            eprintln!("\n[Aspen Error] => {} \n", "系统主目录获取失败".red(), );
-            process::exit(0);
-        }
-        Some(dir) => dir
-    };
-
-    home_dir
+pub fn get_home_dir() -> Result<PathBuf> {
+    dirs::home_dir().ok_or_else(|| AspenError::Io("系统主目录获取失败".to_string()))
 }
 
-pub fn init_aspen() {
-    #[cfg(any(target_os = "macos", target_os = "linux"))]
+pub fn init_aspen() -> Result<()> {
+    // 仅 macOS 使用 expect 脚本登录,linux、windows 使用ssh2原生实现
+    #[cfg(target_os = "macos")]
     {
         let dir = env!("CARGO_PKG_NAME");
 
-        let shell_dir = format!("{}/{}/shell", get_home_dir().to_str().unwrap().to_string(), dir.to_string());
-        generate_folder(shell_dir.clone());
+        let shell_dir = format!("{}/{}/shell", get_home_dir()?.to_str().unwrap(), dir);
+        generate_folder(shell_dir.clone())?;
 
         let controller_path = shell_dir.clone() + "/controller.sh";
-        // 要写入的内容
-        let controller_content = "#!/bin/bash\n\ncurrent_dir=$(dirname \"$(realpath \"$0\")\")\n\neval \"$(which expect) $current_dir/script.ex $1 $2 $3 $4 $5\"";
-
-        generate_shell(controller_path, controller_content);
+        generate_shell(controller_path, CONTROLLER_SCRIPT)?;
 
         let script_path = shell_dir.clone() + "/script.ex";
-        let script_content = "#!/usr/bin/expect\n\nset SERVER_NAME [lindex $argv 0]\nset IP [lindex $argv 1]\nset PORT [lindex $argv 2]\nset USER_NAME [lindex $argv 3]\nset PASSWORD [lindex $argv 4]\n\nspawn ssh -p $PORT $USER_NAME@$IP\n\nexpect {\n    -timeout 300\n    \"*assword\" { send \"$PASSWORD\\r\\n\"; exp_continue ; sleep 3; }\n    \"yes/no\" { send yes\\n\"; exp_continue; }\n    \"Last*\" {\n        puts \"\\nLogin Successful!!!\\n\";\n    }\n    timeout { puts \"Expect was timeout.\"; return }\n}\n\ninteract\n";
-
-        generate_shell(script_path, script_content);
+        generate_shell(script_path, EXPECT_SCRIPT)?;
     }
+
+    Ok(())
 }
 
 // 启动aspen命令
-pub fn run() {
+pub fn run() -> Result<()> {
     // 构建命令详情
     let app = build_cli();
 
@@ -60,8 +62,23 @@ pub fn run() {
     match matches.subcommand() {
         Some(("ssh", sub_matches)) => impl_ssh_action(sub_matches),
         Some(("all", sub_matches)) => impl_servers_table_action(sub_matches),
-        Some(("set-path", sub_matches)) => import_set_servers_path_action(sub_matches),
-        Some(("get-path", sub_matches)) => import_get_servers_path_action(sub_matches),
+        Some(("exec", sub_matches)) => impl_exec_action(sub_matches),
+        Some(("exec-all", sub_matches)) => impl_exec_all_action(sub_matches),
+        Some(("put", sub_matches)) => impl_put_action(sub_matches),
+        Some(("get", sub_matches)) => impl_get_action(sub_matches),
+        Some(("sync", sub_matches)) => impl_sync_action(sub_matches),
+        Some(("tunnel", sub_matches)) => impl_tunnel_action(sub_matches),
+        Some(("rtunnel", sub_matches)) => impl_rtunnel_action(sub_matches),
+        Some(("socks", sub_matches)) => impl_socks_action(sub_matches),
+        Some(("add", sub_matches)) => impl_add_server_action(sub_matches),
+        Some(("edit", sub_matches)) => impl_edit_server_action(sub_matches),
+        Some(("rm", sub_matches)) => impl_rm_server_action(sub_matches),
+        Some(("cp", sub_matches)) => impl_cp_server_action(sub_matches),
+        Some(("import", sub_matches)) => impl_import_action(sub_matches),
+        Some(("export", sub_matches)) => impl_export_action(sub_matches),
+        Some(("path", sub_matches)) => impl_path_action(sub_matches),
+        Some(("vault", sub_matches)) => impl_vault_action(sub_matches),
+        Some(("known-hosts", sub_matches)) => impl_known_hosts_action(sub_matches),
         _ => error_action(),
     }
 }
@@ -84,10 +101,33 @@ pub fn build_cli() -> Command {
         .subcommand(build_ssh_toolbox())
         // 查看已配置的服务器列表
         .subcommand(build_ssh_servers_table_toolbox())
-        // 设置服务器配置文件地址
-        .subcommand(build_set_servers_path_toolbox())
-        // 获取服务器配置文件地址
-        .subcommand(build_get_servers_path_toolbox())
+        // 在服务器上执行命令
+        .subcommand(build_exec_toolbox())
+        .subcommand(build_exec_all_toolbox())
+        // 上传、下载文件
+        .subcommand(build_put_toolbox())
+        .subcommand(build_get_toolbox())
+        // 同步本地目录到服务器
+        .subcommand(build_sync_toolbox())
+        // 端口转发
+        .subcommand(build_tunnel_toolbox())
+        .subcommand(build_rtunnel_toolbox())
+        .subcommand(build_socks_toolbox())
+        // 服务器增删改
+        .subcommand(build_add_server_toolbox())
+        .subcommand(build_edit_server_toolbox())
+        .subcommand(build_rm_server_toolbox())
+        .subcommand(build_cp_server_toolbox())
+        // 从其他配置导入服务器
+        .subcommand(build_import_toolbox())
+        // 导出服务器列表
+        .subcommand(build_export_toolbox())
+        // 服务器配置文件列表
+        .subcommand(build_path_toolbox())
+        // 加密保险库
+        .subcommand(build_vault_toolbox())
+        // 主机公钥管理
+        .subcommand(build_known_hosts_toolbox())
 }
 
 // 构建ssh工具的命令
@@ -95,101 +135,435 @@ fn build_ssh_toolbox() -> Command {
     Command::new("ssh")
         .about("ssh工具箱")
         .args([
-            Arg::new("index").help("输入服务器的 序号").required(false),
+            Arg::new("index").help("输入服务器的 序号、标题、别名 或 关键字").required(false),
+            build_danger_arg(),
         ])
+        .args(build_filter_args())
 }
 
 // 构建查看服务器列表命令
 fn build_ssh_servers_table_toolbox() -> Command {
     Command::new("all")
         .about("查看已配置的服务器列表")
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .help("输出格式,plain 为不带表头与边框的对齐文本,便于 fzf 等工具使用")
+                .value_parser(["table", "json", "csv", "tsv", "plain"])
+                .default_value("table")
+        )
+        .arg(
+            Arg::new("columns")
+                .short('c')
+                .long("columns")
+                .help("输出的列(逗号分隔): id,title,host,port,category,username,password,remark,source,aliases,tags,labels,proxy_jump,identity_file,use_agent,danger")
+        )
+        .arg(Arg::new("no-clear").long("no-clear").help("输出前不清屏(标准输出不是终端时默认不清屏)").action(ArgAction::SetTrue))
+        .arg(Arg::new("show-secrets").long("show-secrets").help("显示密码,默认以 ****** 代替").action(ArgAction::SetTrue))
+        .args(build_filter_args())
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .help("排序方式,last-used 按最近使用时间倒序,未使用过的排在最后")
+                .value_parser(["title", "host", "category", "last-used"])
+        )
+        .arg(
+            Arg::new("group-by")
+                .long("group-by")
+                .help("分组输出,表格格式下每组一张子表并显示数量")
+                .value_parser(["category"])
+        )
+}
+
+// 构建远程执行命令
+fn build_exec_toolbox() -> Command {
+    Command::new("exec")
+        .about("在服务器上执行命令,以远程命令的退出码退出")
+        .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+        .arg(
+            Arg::new("command")
+                .help("要执行的命令,写在 -- 之后")
+                .required(true)
+                .num_args(1..)
+                .last(true)
+                .allow_hyphen_values(true),
+        )
+        .arg(build_danger_arg())
+        .args(build_filter_args())
+}
+
+// 构建批量远程执行命令
+fn build_exec_all_toolbox() -> Command {
+    Command::new("exec-all")
+        .about("在多台服务器上并发执行命令,任意一台失败时以非 0 退出")
+        .arg(
+            Arg::new("selector")
+                .help("选择表达式,逗号分隔且需同时满足,如 all、category=CentOS、tag=web,host!=10.0.0.*,其他键按键值标签匹配,如 env=prod,team!=infra、env in (prod,staging)")
+                .required(true),
+        )
+        .arg(
+            Arg::new("parallel")
+                .short('p')
+                .long("parallel")
+                .help("同时执行的服务器数量")
+                .default_value("8")
+                .value_parser(value_parser!(u64).range(1..)),
+        )
+        .arg(Arg::new("group").short('g').long("group").help("每台服务器执行结束后整块输出,不逐行加前缀").action(ArgAction::SetTrue))
+        .arg(
+            Arg::new("command")
+                .help("要执行的命令,写在 -- 之后")
+                .required(true)
+                .num_args(1..)
+                .last(true)
+                .allow_hyphen_values(true),
+        )
+        .arg(build_danger_arg())
+        .args(build_filter_args())
+}
+
+// 构建上传文件命令
+fn build_put_toolbox() -> Command {
+    Command::new("put")
+        .about("通过 SFTP 上传文件或目录到服务器")
+        .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+        .arg(Arg::new("local").help("本地文件或目录").required(true))
+        .arg(Arg::new("remote").help("远程路径,为已存在的目录时放入该目录下").required(true))
+        .arg(Arg::new("no-resume").long("no-resume").help("不续传,总是完整重新传输").action(ArgAction::SetTrue))
+        .arg(build_danger_arg())
+        .args(build_filter_args())
+}
+
+// 构建下载文件命令
+fn build_get_toolbox() -> Command {
+    Command::new("get")
+        .about("通过 SFTP 从服务器下载文件或目录")
+        .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+        .arg(Arg::new("remote").help("远程文件或目录").required(true))
+        .arg(Arg::new("local").help("本地路径,为已存在的目录时放入该目录下").required(true))
+        .arg(Arg::new("no-resume").long("no-resume").help("不续传,总是完整重新传输").action(ArgAction::SetTrue))
+        .arg(build_danger_arg())
+        .args(build_filter_args())
+}
+
+// 构建目录同步命令
+fn build_sync_toolbox() -> Command {
+    Command::new("sync")
+        .about("将本地目录同步到服务器目录,只传输有变化的文件")
+        .arg(Arg::new("local").help("本地目录").required(true))
+        .arg(Arg::new("destination").help("<服务器>:<远程目录>,服务器支持 序号、标题、别名 或 关键字").required(true))
+        .arg(
+            Arg::new("compare")
+                .short('c')
+                .long("compare")
+                .help("判断文件是否变化的方式")
+                .value_parser(["size", "mtime", "checksum"])
+                .default_value("mtime"),
+        )
+        .arg(Arg::new("delete").long("delete").help("删除远程目录中本地不存在的文件").action(ArgAction::SetTrue))
+        .arg(Arg::new("dry-run").short('n').long("dry-run").help("只列出将要执行的修改").action(ArgAction::SetTrue))
+        .arg(
+            Arg::new("exclude")
+                .short('e')
+                .long("exclude")
+                .help("排除规则(.gitignore 语法),可多次指定;本地目录下的 .aspenignore 会自动读取")
+                .action(ArgAction::Append),
+        )
+        .arg(Arg::new("exclude-from").long("exclude-from").help("从文件读取排除规则(.gitignore 语法)"))
+        .arg(build_danger_arg())
+        .args(build_filter_args())
+}
+
+// 构建本地端口转发命令
+fn build_tunnel_toolbox() -> Command {
+    Command::new("tunnel")
+        .about("打开服务器配置中的本地端口转发(forwards),按 Ctrl-C 退出")
+        .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+        .arg(Arg::new("name").help("只打开指定名称的端口转发").required(false))
+        .arg(build_danger_arg())
+        .args(build_filter_args())
+}
+
+// 构建远程端口转发命令
+fn build_rtunnel_toolbox() -> Command {
+    Command::new("rtunnel")
+        .about("在服务器上监听端口并转发到本地(remote_forwards 或 -R),按 Ctrl-C 退出")
+        .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+        .arg(Arg::new("name").help("只打开指定名称的远程端口转发").required(false))
+        .arg(
+            Arg::new("remote")
+                .short('R')
+                .help("[监听地址:]端口:本地地址:本地端口,可多次指定")
+                .action(ArgAction::Append),
+        )
+        .arg(build_danger_arg())
+        .args(build_filter_args())
+}
+
+// 构建 SOCKS5 代理命令
+fn build_socks_toolbox() -> Command {
+    Command::new("socks")
+        .about("在本地运行经服务器转发的 SOCKS5 代理(同 ssh -D),按 Ctrl-C 退出")
+        .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+        .arg(Arg::new("bind").short('b').long("bind").help("本地监听地址,只写端口时监听 127.0.0.1").default_value("127.0.0.1:1080"))
+        .arg(Arg::new("username").short('u').long("username").help("代理认证用户名"))
+        .arg(Arg::new("password").short('p').long("password").help("代理认证密码(支持 vault:<key> 引用)"))
+        .arg(build_danger_arg())
+        .args(build_filter_args())
+}
+
+// 构建新增服务器命令
+fn build_add_server_toolbox() -> Command {
+    Command::new("add")
+        .about("新增服务器(不带参数时进入交互向导)")
+        .args(build_server_field_args())
+        .arg(Arg::new("interactive").short('i').long("interactive").help("使用交互向导").action(ArgAction::SetTrue))
+}
+
+// 构建编辑服务器命令
+fn build_edit_server_toolbox() -> Command {
+    Command::new("edit")
+        .about("编辑服务器(不带字段参数时进入交互向导)")
+        .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+        .args(build_server_field_args())
+        // --category、--host 用于修改字段,只支持按 --tag、--label 筛选
+        .args(build_filter_args().into_iter().filter(|arg| matches!(arg.get_id().as_str(), "tag" | "label")))
 }
 
-// 构建设置服务器配置地址命令
-fn build_set_servers_path_toolbox() -> Command {
-    let about = format!("设置服务器的配置文件地址({})", "建议绝对地址".green());
+// 构建删除服务器命令
+fn build_rm_server_toolbox() -> Command {
+    Command::new("rm")
+        .about("删除服务器")
+        .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+        .arg(Arg::new("yes").short('y').long("yes").help("跳过确认(按关键字匹配时仍需确认)").action(ArgAction::SetTrue))
+        .args(build_filter_args())
+}
+
+// 构建复制服务器命令
+fn build_cp_server_toolbox() -> Command {
+    Command::new("cp")
+        .about("复制服务器")
+        .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+        .arg(Arg::new("new-title").help("新服务器的标题").required(true))
+        .args(build_filter_args())
+}
+
+// 构建导入服务器命令
+fn build_import_toolbox() -> Command {
+    Command::new("import")
+        .about("从其他配置导入服务器")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("ssh-config")
+                .about("从 OpenSSH 配置文件导入(支持 Include 与通配符 Host)")
+                .arg(Arg::new("path").help("配置文件地址,默认 ~/.ssh/config").required(false))
+                .arg(Arg::new("update").long("update").help("更新已存在的同名服务器(默认跳过)").action(ArgAction::SetTrue))
+                .arg(Arg::new("dry-run").short('n').long("dry-run").help("只预览,不写入").action(ArgAction::SetTrue))
+                .arg(Arg::new("yes").short('y').long("yes").help("跳过确认").action(ArgAction::SetTrue))
+        )
+}
 
-    Command::new("set-path")
-        .about(about)
-        .arg(Arg::new("path").help("请输入服务器配置文件地址(建议绝对地址)").required(true))
+// 构建导出服务器命令
+fn build_export_toolbox() -> Command {
+    Command::new("export")
+        .about("导出服务器列表(OpenSSH 配置、Ansible 清单、CSV、JSON)")
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .help("导出格式,Ansible 清单以分类作为主机组")
+                .value_parser(["ssh-config", "ansible-ini", "ansible-yaml", "csv", "json"])
+                .default_value("json")
+        )
+        .arg(Arg::new("output").short('o').long("output").help("输出文件,默认输出到标准输出"))
+        .arg(Arg::new("no-passwords").long("no-passwords").help("不导出密码与私钥密码").action(ArgAction::SetTrue))
+        .arg(
+            Arg::new("show-secrets")
+                .long("show-secrets")
+                .help("从保险库解析 vault:<key> 引用并导出实际的值,默认 JSON 保留引用,其他格式不导出")
+                .conflicts_with("no-passwords")
+                .action(ArgAction::SetTrue)
+        )
 }
 
-// 构建获取服务器配置地址命令
-fn build_get_servers_path_toolbox() -> Command {
-    Command::new("get-path")
-        .about("获取服务器的配置文件地址")
+// add、edit 共用的服务器字段参数
+fn build_server_field_args() -> Vec<Arg> {
+    vec![
+        Arg::new("title").long("title").help("标题"),
+        Arg::new("category").long("category").help("分类"),
+        Arg::new("host").long("host").help("主机地址"),
+        Arg::new("port").long("port").help("端口"),
+        Arg::new("username").long("username").help("用户名"),
+        Arg::new("password").long("password").help("密码(支持 vault:<key> 引用)"),
+        Arg::new("remark").long("remark").help("备注"),
+        Arg::new("identity-file").long("identity-file").help("私钥文件"),
+        Arg::new("passphrase").long("passphrase").help("私钥密码(支持 vault:<key> 引用)"),
+        Arg::new("aliases").long("aliases").help("别名(逗号分隔)"),
+        Arg::new("tags").long("tags").help("标签(逗号分隔)"),
+        Arg::new("labels").long("labels").help("键值标签(逗号分隔的 key=value,如 env=prod,team=payments)"),
+        Arg::new("proxy-jump").long("proxy-jump").help("跳板机(另一台服务器的标题或别名)"),
+        Arg::new("use-agent")
+            .long("use-agent")
+            .help("使用 ssh-agent 认证")
+            .num_args(0..=1)
+            .default_missing_value("true")
+            .value_parser(value_parser!(bool)),
+        Arg::new("danger")
+            .long("danger")
+            .help("标记为敏感服务器(如生产环境),登录、执行命令、传输文件、端口转发前需输入标题确认")
+            .num_args(0..=1)
+            .default_missing_value("true")
+            .value_parser(value_parser!(bool)),
+    ]
 }
 
-fn error_action() {
-    eprintln!("\n[Aspen Error] => {} \n", "非法指令".red(), );
-    process::exit(0);
+// ssh、exec、put 等命令共用: 跳过敏感服务器的确认
+fn build_danger_arg() -> Arg {
+    Arg::new("yes-i-am-sure")
+        .long("yes-i-am-sure")
+        .help("跳过敏感服务器(danger)的确认,用于非交互环境")
+        .action(ArgAction::SetTrue)
+}
+
+// 按 分类、主机、标签 筛选服务器的参数,可用于 all 及所有需要指定服务器的命令
+fn build_filter_args() -> Vec<Arg> {
+    vec![
+        Arg::new("category").long("category").help("只保留分类匹配的服务器(忽略大小写包含匹配,支持通配符)"),
+        Arg::new("host").long("host").help("只保留主机地址匹配的服务器(忽略大小写包含匹配,支持通配符)"),
+        Arg::new("tag").long("tag").help("只保留带有该标签的服务器,可多次指定且需同时满足").action(ArgAction::Append),
+        Arg::new("label")
+            .long("label")
+            .help("按键值标签筛选,如 env=prod、team!=infra、env in (prod,staging),可逗号分隔或多次指定且需同时满足")
+            .action(ArgAction::Append),
+    ]
+}
+
+// 构建服务器配置文件列表命令
+fn build_path_toolbox() -> Command {
+    Command::new("path")
+        .about("管理服务器配置文件列表(可配置多个,标题相同的服务器以靠后的文件为准)")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("add")
+                .about("添加配置文件(支持通配符,如 ~/servers/*.yaml)")
+                .arg(Arg::new("path").help(format!("配置文件地址({})", "建议绝对地址".green())).required(true))
+                .arg(Arg::new("first").long("first").help("添加到列表开头(优先级最低)").action(ArgAction::SetTrue))
+        )
+        .subcommand(
+            Command::new("rm")
+                .about("从列表中移除配置文件(不删除文件)")
+                .arg(Arg::new("path").help("配置文件地址 或 序号").required(true))
+        )
+        .subcommand(Command::new("list").about("查看配置文件列表"))
+}
+
+// 构建保险库命令
+fn build_vault_toolbox() -> Command {
+    Command::new("vault")
+        .about("加密保险库(服务器配置中以 vault:<key> 引用密码)")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("set")
+                .about("新增或更新条目")
+                .arg(Arg::new("key").help("条目名称").required(true))
+                .arg(Arg::new("value").help("条目的值,不推荐: 会被 ps 与 shell 历史记录(不填则隐藏输入,或通过管道传入)").required(false))
+        )
+        .subcommand(
+            Command::new("get")
+                .about("查看条目的值")
+                .arg(Arg::new("key").help("条目名称").required(true))
+        )
+        .subcommand(
+            Command::new("rm")
+                .about("删除条目")
+                .arg(Arg::new("key").help("条目名称").required(true))
+        )
+        .subcommand(Command::new("list").about("查看全部条目"))
+        .subcommand(Command::new("lock").about("立即锁定保险库,清除缓存的密钥"))
+        .subcommand(
+            Command::new("timeout")
+                .about("设置解锁后密钥的缓存时长")
+                .arg(Arg::new("seconds").help("缓存时长(秒),0 表示不缓存").required(true))
+        )
+}
+
+// 构建主机公钥管理命令
+fn build_known_hosts_toolbox() -> Command {
+    Command::new("known-hosts")
+        .about("管理 ~/.ssh/known_hosts 中的主机公钥")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("list")
+                .about("查看全部记录,指定筛选条件时只显示满足条件的服务器的记录")
+                .args(build_filter_args())
+        )
+        .subcommand(
+            Command::new("accept")
+                .about("获取服务器当前的公钥并信任(替换旧记录)")
+                .arg(Arg::new("target").help("服务器的 序号、标题、别名 或 关键字").required(true))
+                .arg(Arg::new("yes").short('y').long("yes").help("跳过指纹确认").action(ArgAction::SetTrue))
+                .args(build_filter_args())
+        )
+        .subcommand(
+            Command::new("rm")
+                .about("删除主机的记录")
+                .arg(Arg::new("host").help("主机地址").required(true))
+                .arg(Arg::new("port").short('p').long("port").help("端口").default_value("22"))
+        )
+}
+
+fn error_action() -> Result<()> {
+    Err(AspenError::Usage("非法指令".to_string()))
 }
 
 // 构建存储文件夹
-pub fn generate_folder(folder_path: String) {
+pub fn generate_folder(folder_path: String) -> Result<()> {
     // 检查文件夹是否存在，如果不存在则创建
     if !Path::new(&folder_path).exists() {
-        fs::create_dir_all(&folder_path).unwrap();
-        match fs::create_dir_all(&folder_path) {
-            Ok(_) => {}
-            Err(_) => {
-                eprintln!("\n[Aspen Error] => 创建文件夹 {} 失败！ \n", folder_path.red(), );
-                process::exit(0);
-            }
-        }
+        fs::create_dir_all(&folder_path)
+            .map_err(|_| AspenError::Io(format!("创建文件夹 {} 失败！", folder_path)))?;
 
         #[cfg(any(target_os = "macos", target_os = "linux"))]
         {
             // 获取目标文件夹信息
-            let metadata = match fs::metadata(&folder_path) {
-                Ok(meta) => meta,
-                Err(_) => {
-                    eprintln!("\n[Aspen Error] => 获取目标文件夹 {} 相关信息失败！ \n", folder_path.red(), );
-                    process::exit(0);
-                }
-            };
+            let metadata = fs::metadata(&folder_path)
+                .map_err(|_| AspenError::Io(format!("获取目标文件夹 {} 相关信息失败！", folder_path)))?;
 
             // 设置文件夹权限为 775
             let mut permissions = metadata.permissions();
             permissions.set_mode(0o775);
-            match fs::set_permissions(&folder_path, permissions) {
-                Ok(_) => {}
-                Err(_) => {
-                    eprintln!("\n[Aspen Error] => 设置文件夹 {} 权限失败！ \n", folder_path.red(), );
-                    process::exit(0);
-                }
-            }
+            fs::set_permissions(&folder_path, permissions)
+                .map_err(|_| AspenError::Io(format!("设置文件夹 {} 权限失败！", folder_path)))?;
         }
     }
+
+    Ok(())
 }
 
 // 构建脚本
-#[cfg(any(target_os = "macos", target_os = "linux"))]
-fn generate_shell(file_path: String, content: &str) {
-    match fs::metadata(&file_path) {
-        Ok(_) => {}
-        Err(_) => {
+#[cfg(target_os = "macos")]
+fn generate_shell(file_path: String, content: &str) -> Result<()> {
+    // 脚本内容与当前版本一致时无需重新生成
+    match fs::read_to_string(&file_path) {
+        Ok(existing) if existing == content => Ok(()),
+        _ => {
             // 创建文件并打开以进行写入，如果文件不存在则会创建它
-            let mut file = match OpenOptions::new()
+            let mut file = OpenOptions::new()
                 .write(true)
                 .create(true)
+                .truncate(true)
                 .mode(0o775) // 设置权限为 775
-                .open(&file_path) {
-                Ok(file) => file,
-                Err(_) => {
-                    eprintln!("\n[Aspen Error] => {} \n", "创建脚本失败".red(), );
-                    process::exit(0);
-                }
-            };
+                .open(&file_path)
+                .map_err(|_| AspenError::Io("创建脚本失败".to_string()))?;
 
             // 将内容写入文件
-            match file.write_all(content.as_bytes()) {
-                Ok(_) => {}
-                Err(_) => {
-                    eprintln!("\n[Aspen Error] => {} \n", "写入脚本内容失败！".red());
-                    process::exit(0);
-                }
-            }
+            file.write_all(content.as_bytes())
+                .map_err(|_| AspenError::Io("写入脚本内容失败！".to_string()))
         }
     }
-}
\ No newline at end of file
+}
@@ -3,24 +3,36 @@ use std::{
     path::{Path, PathBuf},
     io::{Read, Write},
 };
-use colored::Colorize;
 use serde::{Deserialize, Serialize};
 
 use crate::aspen_module::cli::{generate_folder, get_home_dir};
+use crate::aspen_module::error::{AspenError, Result};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
+    // 服务器配置文件列表(支持通配符),多个文件中标题相同的服务器以靠后的为准
+    #[serde(default)]
+    pub service_config_paths: Vec<String>,
+    // 旧版本的单个配置文件地址,读取时并入 service_config_paths,不再写入
+    #[serde(default, skip_serializing)]
     pub service_config_path: String,
+    // 保险库解锁后密钥的缓存时长(秒),0 表示不缓存
+    #[serde(default = "default_vault_cache_seconds")]
+    pub vault_cache_seconds: u64,
+}
+
+fn default_vault_cache_seconds() -> u64 {
+    300
 }
 
 /**
  * 获取Aspen的配置文件地址
  */
-pub fn get_aspen_config() -> Config {
+pub fn get_aspen_config() -> Result<Config> {
     let dir = env!("CARGO_PKG_NAME");
-    let config_dir = format!("{}/{}/config", get_home_dir().to_str().unwrap().to_string(), dir.to_string());
+    let config_dir = format!("{}/{}/config", get_home_dir()?.to_str().unwrap(), dir);
 
-    generate_folder(config_dir.clone());
+    generate_folder(config_dir.clone())?;
 
     // 命令指定的配置文件位置
     let config_path = format!("{}/aspen_config.json", config_dir);
@@ -28,31 +40,32 @@ pub fn get_aspen_config() -> Config {
     //配置文件存在就直接读取,反之则创建
     if Path::new(&config_path).exists() {
         // 打开文件
-        read_config(&PathBuf::from(config_path.as_str())).unwrap()
+        let mut config = read_config(&PathBuf::from(config_path.as_str()))?;
+
+        let legacy_path = std::mem::take(&mut config.service_config_path);
+        if !legacy_path.is_empty() && !config.service_config_paths.contains(&legacy_path) {
+            config.service_config_paths.insert(0, legacy_path);
+        }
+
+        Ok(config)
     } else {
         // 创建一个 Config 结构体实例
         let config = Config {
+            service_config_paths: Vec::new(),
             service_config_path: "".to_string(),
+            vault_cache_seconds: default_vault_cache_seconds(),
         };
 
         // 将 Config 结构体序列化为 JSON 格式的字符串
-        let json_string: String = match serde_json::to_string_pretty(&config) {
-            Ok(s) => s,
-            Err(_) => {
-                eprintln!("\n[Aspen Error] => {} \n", "错误配置信息!".red());
-                std::process::exit(0);
-            }
-        };
+        let json_string: String = serde_json::to_string_pretty(&config)
+            .map_err(|_| AspenError::Config("错误配置信息!".to_string()))?;
 
-        if let Ok(mut file) = File::create(&config_path) {
-            file.write_all(json_string.as_bytes()).unwrap();
-            // println!("成功创建并写入 JSON 文件: {:?}", config_path);
+        let mut file = File::create(&config_path)
+            .map_err(|_| AspenError::Io("无法创建配置文件!".to_string()))?;
+        file.write_all(json_string.as_bytes())
+            .map_err(|_| AspenError::Io("写入Aspen配置文件失败!".to_string()))?;
 
-            config
-        } else {
-            eprintln!("\n[Aspen Error] => {} \n", "无法创建配置文件!".red());
-            std::process::exit(0);
-        }
+        Ok(config)
     }
 }
 
@@ -60,57 +73,35 @@ pub fn get_aspen_config() -> Config {
 /**
  * 读取指定位置的json文件内容
  */
-pub fn read_config(file_path: &PathBuf) -> Result<Config, Box<dyn std::error::Error>> {
-    if let Ok(mut file) = File::open(&file_path) {
-        // 读取文件内容
-        let mut contents = String::new();
-
-        match file.read_to_string(&mut contents) {
-            Ok(_) => {
-                // 使用 serde_json 解析 JSON
-                Ok(serde_json::from_str(&contents).unwrap())
-            }
-            Err(_) => {
-                eprintln!("\n[Aspen Error] => {} \n", "读取配置json文件失败!".red());
-                std::process::exit(0);
-            }
-        }
-    } else {
-        eprintln!("\n[Aspen Error] => {} \n", "无法打开配置文件!".red());
-        std::process::exit(0);
-    }
+pub fn read_config(file_path: &PathBuf) -> Result<Config> {
+    let mut file = File::open(file_path).map_err(|_| AspenError::Io("无法打开配置文件!".to_string()))?;
+
+    // 读取文件内容
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(|_| AspenError::Io("读取配置json文件失败!".to_string()))?;
+
+    // 使用 serde_json 解析 JSON
+    serde_json::from_str(&contents)
+        .map_err(|e| AspenError::Config(format!("解析Aspen配置文件 {} 失败! ({})", file_path.display(), e)))
 }
 
 /**
  * 写入指定文件内容
  */
-pub fn write_aspen_config(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+pub fn write_aspen_config(config: &Config) -> Result<()> {
     let dir = env!("CARGO_PKG_NAME");
-    let config_path = format!("{}/{}/config/aspen_config.json", get_home_dir().to_str().unwrap().to_string(), dir.to_string());
+    let config_path = format!("{}/{}/config/aspen_config.json", get_home_dir()?.to_str().unwrap(), dir);
 
-    let mut file = match OpenOptions::new().write(true).truncate(true).open(config_path) {
-        Ok(f) => f,
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {} \n", "打开指定配置文件错误!".red());
-            std::process::exit(0);
-        }
-    };
+    let mut file = OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(config_path)
+        .map_err(|_| AspenError::Io("打开指定配置文件错误!".to_string()))?;
 
-    let json_string = match serde_json::to_string_pretty(config) {
-        Ok(s) => s,
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {} \n", "写入的配置信息转为json失败!".red());
-            std::process::exit(0);
-        }
-    };
+    let json_string = serde_json::to_string_pretty(config)
+        .map_err(|_| AspenError::Config("写入的配置信息转为json失败!".to_string()))?;
 
-    match file.write_all(json_string.as_bytes()) {
-        Ok(_) => {
-            Ok(())
-        }
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {} \n", "写入Aspen配置文件失败!".red());
-            std::process::exit(0);
-        }
-    }
+    file.write_all(json_string.as_bytes())
+        .map_err(|_| AspenError::Io("写入Aspen配置文件失败!".to_string()))
 }
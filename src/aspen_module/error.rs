@@ -0,0 +1,78 @@
+use std::{error, fmt, result};
+
+/**
+ * 工具箱统一的错误类型,每种错误对应不同的退出码,便于外部脚本区分失败原因
+ * exec 例外: 与 ssh 一致,远程命令的退出码原样返回,工具自身的错误统一为 255
+ */
+#[derive(Debug)]
+pub enum AspenError {
+    // 命令参数不合法
+    Usage(String),
+    // 服务器配置或 Aspen 配置缺失、格式错误、校验失败
+    Config(String),
+    // 未找到指定的服务器、条目或文件
+    NotFound(String),
+    // 读写本地文件失败
+    Io(String),
+    // 网络不通、链接超时、传输层协议协商失败
+    Network(String),
+    // 主机公钥未被信任或与记录不一致
+    HostKey(String),
+    // 身份认证失败
+    Auth(String),
+    // ssh 通道、SFTP 或端口转发等远程操作失败
+    Remote(String),
+    // 保险库未创建、主密码错误、条目不存在
+    Vault(String),
+    // 用户取消操作
+    Cancelled,
+    // 结果已输出,只需以指定的退出码结束: exec 中远程命令的非 0 退出码,exec-all 中有服务器失败时为 1
+    Exit(i32),
+    // exec 自身的错误(链接、认证等),以 255 退出,不与远程命令的退出码混淆
+    Exec(Box<AspenError>),
+}
+
+pub type Result<T> = result::Result<T, AspenError>;
+
+impl AspenError {
+    /**
+     * 进程退出码,参数错误与 clap 保持一致为 2
+     */
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AspenError::Usage(_) => 2,
+            AspenError::Config(_) => 3,
+            AspenError::NotFound(_) => 4,
+            AspenError::Io(_) => 5,
+            AspenError::Network(_) => 6,
+            AspenError::HostKey(_) => 7,
+            AspenError::Auth(_) => 8,
+            AspenError::Remote(_) => 9,
+            AspenError::Vault(_) => 10,
+            AspenError::Cancelled => 11,
+            AspenError::Exit(code) => *code,
+            AspenError::Exec(_) => 255,
+        }
+    }
+}
+
+impl fmt::Display for AspenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AspenError::Usage(message)
+            | AspenError::Config(message)
+            | AspenError::NotFound(message)
+            | AspenError::Io(message)
+            | AspenError::Network(message)
+            | AspenError::HostKey(message)
+            | AspenError::Auth(message)
+            | AspenError::Remote(message)
+            | AspenError::Vault(message) => write!(f, "{}", message),
+            AspenError::Cancelled => write!(f, "已取消"),
+            AspenError::Exit(code) => write!(f, "退出码 {}", code),
+            AspenError::Exec(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for AspenError {}
@@ -1,2 +1,3 @@
 pub mod config;
-pub mod cli;
\ No newline at end of file
+pub mod cli;
+pub mod error;
@@ -0,0 +1,5 @@
+pub mod ssh_module;
+pub mod aspen_module;
+pub mod vault_module;
+pub mod sftp_module;
+pub mod tunnel_module;
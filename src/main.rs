@@ -1,21 +1,23 @@
-mod ssh_module;
-mod aspen_module;
-
-#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+use std::process;
 use colored::Colorize;
 
-use crate::aspen_module::cli::{run, init_aspen};
+use aspentool::aspen_module::cli::{run, init_aspen};
+use aspentool::aspen_module::error::AspenError;
 
 fn main() {
     // 排除Windows、MacOs、linux之外的系统
     #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
     {
         eprintln!("\n[Aspen Error] => {}\n", "暂不支持 Windows、MacOs、Linux 之外的系统".red());
-        std::process::exit(0);
+        process::exit(1);
     }
 
-    // 初始化命令行工具
-    init_aspen();
-
-    run();
-}
\ No newline at end of file
+    // 初始化命令行工具,失败时按错误类型返回不同的退出码
+    if let Err(e) = init_aspen().and_then(|_| run()) {
+        // Exit 表示结果已输出,只需设置退出码
+        if !matches!(e, AspenError::Exit(_)) {
+            eprintln!("\n[Aspen Error] => {}\n", e.to_string().red());
+        }
+        process::exit(e.exit_code());
+    }
+}
@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+use clap::ArgMatches;
+use colored::Colorize;
+use ssh2::{Session, Sftp};
+
+use crate::aspen_module::error::{AspenError, Result};
+use crate::sftp_module::transfer::{
+    download, plan_download, plan_upload, remote_is_dir, remote_join, upload, EntryKind, Progress, TransferEntry,
+};
+use crate::ssh_module::config::{get_config, ServerConfig};
+use crate::ssh_module::guard::confirm_dangerous;
+use crate::ssh_module::selector::Selector;
+use crate::ssh_module::session::connect_session;
+use crate::ssh_module::target::resolve_target;
+
+/**
+ * put 命令实现: 上传本地文件或目录到服务器
+ */
+pub fn impl_put_action(matches: &ArgMatches) -> Result<()> {
+    let config = resolve_server(matches)?;
+    confirm_dangerous(&config, "上传", matches.get_flag("yes-i-am-sure"))?;
+    let local = PathBuf::from(matches.get_one::<String>("local").unwrap());
+    let remote = PathBuf::from(matches.get_one::<String>("remote").unwrap());
+    let resume = !matches.get_flag("no-resume");
+
+    if !local.exists() {
+        return Err(AspenError::NotFound(format!("本地文件 {} 不存在!", local.display())));
+    }
+
+    let sess = connect_session(&config)?;
+    let sftp = open_sftp(&sess)?;
+
+    // 与 scp 一致,目标为已存在的目录时放入该目录下
+    let remote = if remote_is_dir(&sftp, &remote) {
+        remote_join(&remote, &file_name(&local)?)
+    } else {
+        remote
+    };
+
+    let entries = plan_upload(&local, &remote).map_err(AspenError::Io)?;
+    let progress = Progress::new(&entries);
+    let result = upload(&sftp, &entries, resume, &progress);
+    progress.finish();
+    result.map_err(AspenError::Remote)?;
+
+    print_summary("上传", &entries, &config, &remote);
+    Ok(())
+}
+
+/**
+ * get 命令实现: 从服务器下载文件或目录
+ */
+pub fn impl_get_action(matches: &ArgMatches) -> Result<()> {
+    let config = resolve_server(matches)?;
+    confirm_dangerous(&config, "下载", matches.get_flag("yes-i-am-sure"))?;
+    let remote = PathBuf::from(matches.get_one::<String>("remote").unwrap());
+    let local = PathBuf::from(matches.get_one::<String>("local").unwrap());
+    let resume = !matches.get_flag("no-resume");
+
+    let sess = connect_session(&config)?;
+    let sftp = open_sftp(&sess)?;
+
+    let local = if local.is_dir() {
+        local.join(file_name(&remote)?)
+    } else {
+        local
+    };
+
+    let entries = plan_download(&sftp, &remote, &local).map_err(AspenError::Remote)?;
+    let progress = Progress::new(&entries);
+    let result = download(&sftp, &entries, resume, &progress);
+    progress.finish();
+    result.map_err(AspenError::Remote)?;
+
+    print_summary("下载", &entries, &config, &local);
+    Ok(())
+}
+
+fn resolve_server(matches: &ArgMatches) -> Result<ServerConfig> {
+    let configs = get_config()?;
+
+    resolve_target(&configs, matches.get_one::<String>("target").unwrap(), &Selector::from_filter_args(matches)?)
+}
+
+/**
+ * 在已认证的会话上打开 SFTP 子系统
+ */
+pub fn open_sftp(sess: &Session) -> Result<Sftp> {
+    sess.sftp()
+        .map_err(|e| AspenError::Remote(format!("打开 SFTP 子系统失败: {}", e.message())))
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    match path.file_name() {
+        Some(name) => Ok(name.to_string_lossy().to_string()),
+        None => Err(AspenError::Usage(format!("无法确定 {} 的文件名!", path.display()))),
+    }
+}
+
+fn print_summary(action: &str, entries: &[TransferEntry], config: &ServerConfig, target: &Path) {
+    let files: Vec<&TransferEntry> = entries.iter().filter(|entry| entry.kind == EntryKind::File).collect();
+    let bytes: u64 = files.iter().map(|entry| entry.size).sum();
+
+    println!(
+        "\n[Aspen Success] ==> 已{} {} 个文件({} 字节) 【 {} 】{}\n",
+        action, files.len(), bytes, config.title.green(), target.display()
+    );
+}
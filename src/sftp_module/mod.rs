@@ -0,0 +1,3 @@
+pub mod transfer;
+pub mod command;
+pub mod sync;
@@ -0,0 +1,605 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+use clap::ArgMatches;
+use colored::Colorize;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use prettytable::{row, Table};
+use sha2::{Digest, Sha256};
+use ssh2::{Session, Sftp};
+
+use crate::aspen_module::error::AspenError;
+use crate::sftp_module::command::open_sftp;
+use crate::sftp_module::transfer::{
+    local_entry, remote_is_dir, remote_join, upload, EntryKind, Progress, TransferEntry,
+};
+use crate::ssh_module::command::table_format;
+use crate::ssh_module::config::get_config;
+use crate::ssh_module::exec::run_command;
+use crate::ssh_module::guard::confirm_dangerous;
+use crate::ssh_module::selector::Selector;
+use crate::ssh_module::session::connect_session;
+use crate::ssh_module::target::resolve_target;
+
+// 本地目录下默认读取的排除规则文件
+const IGNORE_FILE: &str = ".aspenignore";
+
+// 一次远程 sha256sum 校验的文件数量
+const CHECKSUM_BATCH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compare {
+    // 大小不同即传输
+    Size,
+    // 大小或修改时间不同即传输
+    Mtime,
+    // 大小或内容校验和不同即传输
+    Checksum,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Mkdir,
+    Upload,
+    Delete,
+}
+
+impl Action {
+    fn name(&self) -> &'static str {
+        match self {
+            Action::Mkdir => "创建目录",
+            Action::Upload => "上传",
+            Action::Delete => "删除",
+        }
+    }
+}
+
+// 同步计划中的一项
+struct Change {
+    action: Action,
+    path: String,
+    size: u64,
+    reason: &'static str,
+}
+
+// 同步选项,ignore 为合并后的排除规则
+struct SyncOptions<'a> {
+    compare: Compare,
+    delete: bool,
+    ignore: &'a Gitignore,
+}
+
+// 同步计划
+struct SyncPlan {
+    changes: Vec<Change>,
+    // 需要上传(或创建目录)的本地路径
+    uploads: HashSet<PathBuf>,
+    // 需要删除的远程相对路径及是否为目录,子项在父目录之前
+    deletions: Vec<(String, bool)>,
+}
+
+// 远程目录下已存在的文件或目录
+struct RemoteEntry {
+    is_dir: bool,
+    size: u64,
+    mtime: u64,
+}
+
+/**
+ * sync 命令实现: 将本地目录同步到服务器目录,只传输有变化的文件
+ */
+pub fn impl_sync_action(matches: &ArgMatches) -> Result<(), AspenError> {
+    let local = PathBuf::from(matches.get_one::<String>("local").unwrap());
+    let destination = matches.get_one::<String>("destination").unwrap();
+    let compare = match matches.get_one::<String>("compare").unwrap().as_str() {
+        "size" => Compare::Size,
+        "checksum" => Compare::Checksum,
+        _ => Compare::Mtime,
+    };
+    let delete = matches.get_flag("delete");
+    let dry_run = matches.get_flag("dry-run");
+
+    if !local.is_dir() {
+        return Err(AspenError::NotFound(format!("本地目录 {} 不存在!", local.display())));
+    }
+
+    let (target, remote) = match destination.split_once(':') {
+        Some((target, remote)) if !target.is_empty() && !remote.is_empty() => (target, PathBuf::from(remote)),
+        _ => return Err(AspenError::Usage("目标格式应为 <服务器>:<远程目录>".to_string())),
+    };
+
+    let excludes: Vec<String> = matches.get_many::<String>("exclude").map(|values| values.cloned().collect()).unwrap_or_default();
+    let ignore = build_ignore(&local, &excludes, matches.get_one::<String>("exclude-from")).map_err(AspenError::Config)?;
+
+    let configs = get_config()?;
+    let config = resolve_target(&configs, target, &Selector::from_filter_args(matches)?)?;
+    // 只列出修改时不需要确认
+    if !dry_run {
+        confirm_dangerous(&config, "同步", matches.get_flag("yes-i-am-sure"))?;
+    }
+    let sess = connect_session(&config)?;
+    let sftp = open_sftp(&sess)?;
+
+    // 远程目录不存在时先创建
+    let remote_exists = remote_is_dir(&sftp, &remote);
+    let metadata = fs::metadata(&local)
+        .map_err(|e| AspenError::Io(format!("读取本地目录 {} 失败: {}", local.display(), e)))?;
+    let mut local_entries = vec![local_entry(EntryKind::Dir, &local, &remote, &metadata)];
+    plan_local(&local, &remote, &ignore, &mut local_entries).map_err(AspenError::Io)?;
+    let remote_entries = list_remote(&sftp, &remote).map_err(AspenError::Remote)?;
+
+    let options = SyncOptions { compare, delete, ignore: &ignore };
+    let SyncPlan { mut changes, uploads, deletions } =
+        plan_sync(&local, &local_entries, remote_exists, &remote_entries, &options, |candidates| {
+            let remote_sums = remote_checksums(&sess, &sftp, candidates);
+            let mut same: HashSet<String> = HashSet::new();
+            for (path, entry) in candidates {
+                if remote_sums.get(path) == Some(&local_checksum(&entry.source)?) {
+                    same.insert(path.clone());
+                }
+            }
+            Ok(same)
+        })
+        .map_err(AspenError::Io)?;
+
+    if changes.is_empty() {
+        println!("\n[Aspen Success] ==> 【 {} 】{} 已是最新\n", config.title.green(), remote.display());
+        return Ok(());
+    }
+
+    print_changes_table(&mut changes);
+    if dry_run {
+        println!("\n[Aspen Success] ==> {}\n", "试运行,未做任何修改".green());
+        return Ok(());
+    }
+
+    // 按本地遍历顺序上传,保证目录先于其中的文件创建
+    let uploads: Vec<TransferEntry> = local_entries.iter().filter(|entry| uploads.contains(&entry.source)).cloned().collect();
+    let progress = Progress::new(&uploads);
+    let result = upload(&sftp, &uploads, false, &progress);
+    progress.finish();
+    result.map_err(AspenError::Remote)?;
+
+    for (path, is_dir) in &deletions {
+        let target = remote_join(&remote, path);
+        let result = if *is_dir { sftp.rmdir(&target) } else { sftp.unlink(&target) };
+        if let Err(e) = result {
+            return Err(AspenError::Remote(format!("删除远程文件 {} 失败: {}", target.display(), e.message())));
+        }
+    }
+
+    println!(
+        "\n[Aspen Success] ==> 已同步到【 {} 】{}: 上传 {} 项,删除 {} 项\n",
+        config.title.green(), remote.display(), uploads.len(), deletions.len()
+    );
+    Ok(())
+}
+
+// 对照本地与远程条目生成同步计划,不访问服务器
+// local_entries 的第一项为本地根目录;checksum 方式下大小相同的文件交给 same_content,返回其中内容一致的相对路径
+fn plan_sync(
+    local: &Path,
+    local_entries: &[TransferEntry],
+    remote_exists: bool,
+    remote_entries: &BTreeMap<String, RemoteEntry>,
+    options: &SyncOptions,
+    same_content: impl FnOnce(&[(String, &TransferEntry)]) -> Result<HashSet<String>, String>,
+) -> Result<SyncPlan, String> {
+    let mut changes: Vec<Change> = Vec::new();
+    let mut uploads: HashSet<PathBuf> = HashSet::new();
+    let mut checksum_candidates: Vec<(String, &TransferEntry)> = Vec::new();
+
+    for entry in local_entries {
+        if entry.source == local {
+            if !remote_exists {
+                changes.push(Change { action: Action::Mkdir, path: ".".to_string(), size: 0, reason: "远程不存在" });
+                uploads.insert(entry.source.clone());
+            }
+            continue;
+        }
+
+        let path = relative_path(local, &entry.source);
+        let existing = remote_entries.get(&path);
+
+        match entry.kind {
+            EntryKind::Dir => {
+                if !matches!(existing, Some(remote) if remote.is_dir) {
+                    changes.push(Change { action: Action::Mkdir, path, size: 0, reason: "远程不存在" });
+                    uploads.insert(entry.source.clone());
+                }
+            }
+            EntryKind::File => {
+                let reason = match existing {
+                    None => Some("远程不存在"),
+                    Some(remote) if remote.is_dir => Some("远程为目录"),
+                    Some(remote) if remote.size != entry.size => Some("大小不同"),
+                    Some(remote) if options.compare == Compare::Mtime && remote.mtime != entry.mtime => Some("修改时间不同"),
+                    Some(_) if options.compare == Compare::Checksum => {
+                        checksum_candidates.push((path.clone(), entry));
+                        None
+                    }
+                    Some(_) => None,
+                };
+
+                if let Some(reason) = reason {
+                    changes.push(Change { action: Action::Upload, path, size: entry.size, reason });
+                    uploads.insert(entry.source.clone());
+                }
+            }
+        }
+    }
+
+    if !checksum_candidates.is_empty() {
+        let same = same_content(&checksum_candidates)?;
+        for (path, entry) in checksum_candidates {
+            if !same.contains(&path) {
+                changes.push(Change { action: Action::Upload, path, size: entry.size, reason: "校验和不同" });
+                uploads.insert(entry.source.clone());
+            }
+        }
+    }
+
+    // 远程多余的文件(被排除的除外),子项在父目录之前删除
+    let mut deletions: Vec<(String, bool)> = Vec::new();
+    if options.delete {
+        let local_paths: HashSet<String> = local_entries.iter().map(|entry| relative_path(local, &entry.source)).collect();
+        for (path, entry) in remote_entries.iter().rev() {
+            if local_paths.contains(path) || options.ignore.matched_path_or_any_parents(local.join(path), entry.is_dir).is_ignore() {
+                continue;
+            }
+            changes.push(Change { action: Action::Delete, path: path.clone(), size: entry.size, reason: "本地不存在" });
+            deletions.push((path.clone(), entry.is_dir));
+        }
+    }
+
+    Ok(SyncPlan { changes, uploads, deletions })
+}
+
+// 命令行 --exclude、--exclude-from 与本地目录下的 .aspenignore 合并为一组规则
+fn build_ignore(local: &Path, excludes: &[String], exclude_from: Option<&String>) -> Result<Gitignore, String> {
+    let mut builder = GitignoreBuilder::new(local);
+
+    let ignore_file = local.join(IGNORE_FILE);
+    if ignore_file.is_file() {
+        if let Some(e) = builder.add(&ignore_file) {
+            return Err(format!("读取排除规则 {} 失败: {}", ignore_file.display(), e));
+        }
+    }
+    if let Some(file) = exclude_from {
+        if let Some(e) = builder.add(file) {
+            return Err(format!("读取排除规则 {} 失败: {}", file, e));
+        }
+    }
+    for pattern in excludes {
+        builder.add_line(None, pattern).map_err(|e| format!("排除规则 {} 不合法: {}", pattern, e))?;
+    }
+
+    builder.build().map_err(|e| format!("排除规则不合法: {}", e))
+}
+
+// 本地目录下需要同步的条目(不含根目录与被排除的路径),目录在其内容之前
+fn plan_local(local: &Path, remote: &Path, ignore: &Gitignore, entries: &mut Vec<TransferEntry>) -> Result<(), String> {
+    let error = |path: &Path, e: io::Error| format!("读取本地文件 {} 失败: {}", path.display(), e);
+
+    let mut children: Vec<fs::DirEntry> = fs::read_dir(local)
+        .and_then(|dir| dir.collect::<io::Result<_>>())
+        .map_err(|e| error(local, e))?;
+    children.sort_by_key(|child| child.file_name());
+
+    for child in children {
+        let path = child.path();
+        let name = child.file_name().to_string_lossy().to_string();
+        let metadata = fs::metadata(&path).map_err(|e| error(&path, e))?;
+        if name == IGNORE_FILE || ignore.matched(&path, metadata.is_dir()).is_ignore() {
+            continue;
+        }
+
+        let target = remote_join(remote, &name);
+        if metadata.is_dir() {
+            entries.push(local_entry(EntryKind::Dir, &path, &target, &metadata));
+            plan_local(&path, &target, ignore, entries)?;
+        } else if metadata.is_file() {
+            entries.push(local_entry(EntryKind::File, &path, &target, &metadata));
+        }
+    }
+
+    Ok(())
+}
+
+// 远程目录下的全部条目,按相对路径排序;目录不存在时为空
+fn list_remote(sftp: &Sftp, remote: &Path) -> Result<BTreeMap<String, RemoteEntry>, String> {
+    let mut entries: BTreeMap<String, RemoteEntry> = BTreeMap::new();
+
+    match sftp.stat(remote) {
+        Ok(stat) if stat.is_dir() => walk_remote(sftp, remote, "", &mut entries)?,
+        Ok(_) => return Err(format!("远程路径 {} 不是目录!", remote.display())),
+        Err(_) => {}
+    }
+
+    Ok(entries)
+}
+
+fn walk_remote(sftp: &Sftp, dir: &Path, prefix: &str, entries: &mut BTreeMap<String, RemoteEntry>) -> Result<(), String> {
+    let children = sftp
+        .readdir(dir)
+        .map_err(|e| format!("读取远程目录 {} 失败: {}", dir.display(), e.message()))?;
+
+    for (path, stat) in children {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => continue,
+        };
+        if name == "." || name == ".." {
+            continue;
+        }
+
+        let relative = if prefix.is_empty() { name.clone() } else { format!("{}/{}", prefix, name) };
+        let entry = RemoteEntry {
+            is_dir: stat.is_dir(),
+            size: stat.size.unwrap_or(0),
+            mtime: stat.mtime.unwrap_or(0),
+        };
+
+        if entry.is_dir {
+            walk_remote(sftp, &remote_join(dir, &name), &relative, entries)?;
+        }
+        entries.insert(relative, entry);
+    }
+
+    Ok(())
+}
+
+// 相对本地根目录的路径,统一使用 / 分隔
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+fn local_checksum(path: &Path) -> Result<String, String> {
+    let error = |e: io::Error| format!("读取本地文件 {} 失败: {}", path.display(), e);
+    let mut file = File::open(path).map_err(error)?;
+
+    hash_reader(&mut file).map_err(error)
+}
+
+// 优先在服务器上执行 sha256sum,失败时通过 SFTP 读取文件计算
+fn remote_checksums(sess: &Session, sftp: &Sftp, files: &[(String, &TransferEntry)]) -> HashMap<String, String> {
+    let mut sums: HashMap<String, String> = HashMap::new();
+
+    for batch in files.chunks(CHECKSUM_BATCH) {
+        let targets: Vec<String> = batch.iter().map(|(_, entry)| entry.target.to_string_lossy().to_string()).collect();
+        let command = format!(
+            "sha256sum -- {}",
+            targets.iter().map(|target| shell_quote(target)).collect::<Vec<String>>().join(" ")
+        );
+
+        let mut output: Vec<u8> = Vec::new();
+        if let Ok(0) = run_command(sess, &command, |data| output.extend_from_slice(data), |_| {}) {
+            let output = String::from_utf8_lossy(&output);
+            for line in output.lines() {
+                if let Some((sum, target)) = line.split_once("  ") {
+                    if let Some(index) = targets.iter().position(|t| t == target) {
+                        sums.insert(batch[index].0.clone(), sum.to_string());
+                    }
+                }
+            }
+        }
+
+        for (path, entry) in batch {
+            if sums.contains_key(path) {
+                continue;
+            }
+            if let Some(sum) = sftp.open(&entry.target).ok().and_then(|mut file| hash_reader(&mut file).ok()) {
+                sums.insert(path.clone(), sum);
+            }
+        }
+    }
+
+    sums
+}
+
+fn hash_reader(reader: &mut impl Read) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 32 * 1024];
+
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// 单引号包裹,交给远程 shell 时原样传递
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn print_changes_table(changes: &mut [Change]) {
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(row!["Action", "Path", "Size", "Reason"]);
+
+    for change in changes.iter() {
+        let size = if change.action == Action::Mkdir { String::new() } else { change.size.to_string() };
+        table.add_row(row![change.action.name(), change.path, size, change.reason]);
+    }
+
+    table.printstd();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCAL: &str = "/work/site";
+
+    fn local(kind: EntryKind, path: &str, size: u64, mtime: u64) -> TransferEntry {
+        let source = if path.is_empty() { PathBuf::from(LOCAL) } else { Path::new(LOCAL).join(path) };
+        TransferEntry {
+            kind,
+            target: remote_join(Path::new("/data/www"), path),
+            source,
+            size,
+            mode: 0o644,
+            mtime,
+        }
+    }
+
+    fn locals() -> Vec<TransferEntry> {
+        vec![
+            local(EntryKind::Dir, "", 0, 0),
+            local(EntryKind::File, "index.html", 10, 100),
+            local(EntryKind::Dir, "css", 0, 100),
+            local(EntryKind::File, "css/app.css", 20, 100),
+        ]
+    }
+
+    fn remote(is_dir: bool, size: u64, mtime: u64) -> RemoteEntry {
+        RemoteEntry { is_dir, size, mtime }
+    }
+
+    fn plan(
+        remote_entries: &BTreeMap<String, RemoteEntry>,
+        compare: Compare,
+        delete: bool,
+        excludes: &[&str],
+        same: &[&str],
+    ) -> Vec<(Action, String, &'static str)> {
+        let excludes: Vec<String> = excludes.iter().map(|pattern| pattern.to_string()).collect();
+        let ignore = build_ignore(Path::new(LOCAL), &excludes, None).unwrap();
+        let options = SyncOptions { compare, delete, ignore: &ignore };
+        let same: HashSet<String> = same.iter().map(|path| path.to_string()).collect();
+
+        plan_sync(Path::new(LOCAL), &locals(), true, remote_entries, &options, |_| Ok(same))
+            .unwrap()
+            .changes
+            .into_iter()
+            .map(|change| (change.action, change.path, change.reason))
+            .collect()
+    }
+
+    fn synced() -> BTreeMap<String, RemoteEntry> {
+        BTreeMap::from([
+            ("index.html".to_string(), remote(false, 10, 100)),
+            ("css".to_string(), remote(true, 0, 100)),
+            ("css/app.css".to_string(), remote(false, 20, 100)),
+        ])
+    }
+
+    #[test]
+    fn up_to_date_directory_has_no_changes() {
+        assert!(plan(&synced(), Compare::Mtime, true, &[], &[]).is_empty());
+    }
+
+    #[test]
+    fn missing_remote_root_and_entries_are_created() {
+        let ignore = build_ignore(Path::new(LOCAL), &[], None).unwrap();
+        let options = SyncOptions { compare: Compare::Mtime, delete: false, ignore: &ignore };
+        let plan = plan_sync(Path::new(LOCAL), &locals(), false, &BTreeMap::new(), &options, |_| unreachable!()).unwrap();
+
+        let paths: Vec<&str> = plan.changes.iter().map(|change| change.path.as_str()).collect();
+        assert_eq!(paths, vec![".", "index.html", "css", "css/app.css"]);
+        assert_eq!(plan.uploads.len(), 4);
+        assert!(plan.deletions.is_empty());
+    }
+
+    #[test]
+    fn compares_by_size_mtime_or_checksum() {
+        let mut remote_entries = synced();
+        remote_entries.insert("index.html".to_string(), remote(false, 10, 50));
+        remote_entries.insert("css/app.css".to_string(), remote(false, 21, 100));
+
+        assert_eq!(plan(&remote_entries, Compare::Size, false, &[], &[]), vec![
+            (Action::Upload, "css/app.css".to_string(), "大小不同"),
+        ]);
+        assert_eq!(plan(&remote_entries, Compare::Mtime, false, &[], &[]), vec![
+            (Action::Upload, "index.html".to_string(), "修改时间不同"),
+            (Action::Upload, "css/app.css".to_string(), "大小不同"),
+        ]);
+
+        // checksum 方式忽略修改时间,只有大小相同的文件才比较内容
+        assert_eq!(plan(&remote_entries, Compare::Checksum, false, &[], &["index.html"]), vec![
+            (Action::Upload, "css/app.css".to_string(), "大小不同"),
+        ]);
+        assert_eq!(plan(&remote_entries, Compare::Checksum, false, &[], &[]), vec![
+            (Action::Upload, "css/app.css".to_string(), "大小不同"),
+            (Action::Upload, "index.html".to_string(), "校验和不同"),
+        ]);
+    }
+
+    #[test]
+    fn remote_directory_in_place_of_a_file_is_replaced() {
+        let mut remote_entries = synced();
+        remote_entries.insert("index.html".to_string(), remote(true, 0, 100));
+
+        assert_eq!(plan(&remote_entries, Compare::Mtime, false, &[], &[]), vec![
+            (Action::Upload, "index.html".to_string(), "远程为目录"),
+        ]);
+    }
+
+    #[test]
+    fn delete_removes_extra_remote_entries_children_first() {
+        let mut remote_entries = synced();
+        remote_entries.insert("old".to_string(), remote(true, 0, 100));
+        remote_entries.insert("old/a.js".to_string(), remote(false, 5, 100));
+        remote_entries.insert("stale.txt".to_string(), remote(false, 1, 100));
+
+        assert!(plan(&remote_entries, Compare::Mtime, false, &[], &[]).is_empty());
+
+        let ignore = build_ignore(Path::new(LOCAL), &[], None).unwrap();
+        let options = SyncOptions { compare: Compare::Mtime, delete: true, ignore: &ignore };
+        let plan = plan_sync(Path::new(LOCAL), &locals(), true, &remote_entries, &options, |_| unreachable!()).unwrap();
+        assert_eq!(plan.deletions, vec![
+            ("stale.txt".to_string(), false),
+            ("old/a.js".to_string(), false),
+            ("old".to_string(), true),
+        ]);
+        assert!(plan.uploads.is_empty());
+    }
+
+    #[test]
+    fn excluded_remote_entries_are_kept() {
+        let mut remote_entries = synced();
+        remote_entries.insert("debug.log".to_string(), remote(false, 1, 100));
+        remote_entries.insert("uploads".to_string(), remote(true, 0, 100));
+        remote_entries.insert("uploads/a.png".to_string(), remote(false, 1, 100));
+        remote_entries.insert("stale.txt".to_string(), remote(false, 1, 100));
+
+        assert_eq!(plan(&remote_entries, Compare::Mtime, true, &["*.log", "uploads/"], &[]), vec![
+            (Action::Delete, "stale.txt".to_string(), "本地不存在"),
+        ]);
+    }
+
+    #[test]
+    fn excluded_local_entries_are_not_planned() {
+        let dir = std::env::temp_dir().join(format!("aspen-sync-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("node_modules")).unwrap();
+        fs::write(dir.join("node_modules/lib.js"), "x").unwrap();
+        fs::write(dir.join("app.js"), "x").unwrap();
+        fs::write(dir.join("debug.log"), "x").unwrap();
+        fs::write(dir.join(IGNORE_FILE), "node_modules/\n").unwrap();
+
+        let ignore = build_ignore(&dir, &["*.log".to_string()], None).unwrap();
+        let mut entries: Vec<TransferEntry> = Vec::new();
+        plan_local(&dir, Path::new("/data/www"), &ignore, &mut entries).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        let paths: Vec<String> = entries.iter().map(|entry| relative_path(&dir, &entry.source)).collect();
+        assert_eq!(paths, vec!["app.js"]);
+        assert_eq!(entries[0].target, PathBuf::from("/data/www/app.js"));
+    }
+}
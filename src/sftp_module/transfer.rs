@@ -0,0 +1,476 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use filetime::FileTime;
+use indicatif::{ProgressBar, ProgressStyle};
+use ssh2::{FileStat, OpenFlags, OpenType, Sftp};
+
+const BUFFER_SIZE: usize = 32 * 1024;
+
+// 未能获取到权限时使用的默认值
+const DEFAULT_FILE_MODE: u32 = 0o644;
+const DEFAULT_DIR_MODE: u32 = 0o755;
+
+// 传输中的文件先写入 <目标>.aspen-part,完成后再重命名为目标文件,续传只针对该临时文件
+const PART_SUFFIX: &str = ".aspen-part";
+// 临时文件旁记录开始传输时源文件的大小与修改时间,源文件变化后不再续传
+const STAMP_SUFFIX: &str = ".aspen-part.src";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Dir,
+    File,
+}
+
+/**
+ * 待传输的一个文件或目录
+ * source、target 分别为源端、目标端路径,远程路径统一使用 / 分隔
+ */
+#[derive(Debug, Clone)]
+pub struct TransferEntry {
+    pub kind: EntryKind,
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub size: u64,
+    pub mode: u32,
+    pub mtime: u64,
+}
+
+/**
+ * 传输进度条,按字节统计全部文件
+ */
+pub struct Progress {
+    bar: ProgressBar,
+}
+
+impl Progress {
+    pub fn new(entries: &[TransferEntry]) -> Progress {
+        let total = entries.iter().filter(|entry| entry.kind == EntryKind::File).map(|entry| entry.size).sum();
+        let bar = ProgressBar::new(total);
+        bar.set_style(
+            ProgressStyle::with_template("{msg:30!} [{bar:30.green/white}] {bytes}/{total_bytes} {bytes_per_sec} {eta}")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+
+        Progress { bar }
+    }
+
+    fn start(&self, entry: &TransferEntry) {
+        let name = entry.source.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        self.bar.set_message(name);
+    }
+
+    fn inc(&self, bytes: u64) {
+        self.bar.inc(bytes);
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/**
+ * 拼接远程路径
+ */
+pub fn remote_join(base: &Path, name: &str) -> PathBuf {
+    let base = base.to_string_lossy();
+    if base.is_empty() {
+        PathBuf::from(name)
+    } else {
+        PathBuf::from(format!("{}/{}", base.trim_end_matches('/'), name))
+    }
+}
+
+/**
+ * 远程路径是否为已存在的目录
+ */
+pub fn remote_is_dir(sftp: &Sftp, path: &Path) -> bool {
+    matches!(sftp.stat(path), Ok(stat) if stat.is_dir())
+}
+
+/**
+ * 列出本地路径(文件或目录)下需要上传的全部条目,目录在其内容之前
+ */
+pub fn plan_upload(local: &Path, remote: &Path) -> Result<Vec<TransferEntry>, String> {
+    let mut entries: Vec<TransferEntry> = Vec::new();
+    walk_local(local, remote, &mut entries).map_err(|e| format!("读取本地文件 {} 失败: {}", local.display(), e))?;
+
+    Ok(entries)
+}
+
+fn walk_local(local: &Path, remote: &Path, entries: &mut Vec<TransferEntry>) -> io::Result<()> {
+    let metadata = fs::metadata(local)?;
+
+    if metadata.is_dir() {
+        entries.push(local_entry(EntryKind::Dir, local, remote, &metadata));
+
+        let mut children: Vec<fs::DirEntry> = fs::read_dir(local)?.collect::<io::Result<_>>()?;
+        children.sort_by_key(|child| child.file_name());
+        for child in children {
+            let name = child.file_name().to_string_lossy().to_string();
+            walk_local(&child.path(), &remote_join(remote, &name), entries)?;
+        }
+    } else if metadata.is_file() {
+        entries.push(local_entry(EntryKind::File, local, remote, &metadata));
+    }
+
+    Ok(())
+}
+
+/**
+ * 由本地文件信息生成上传条目
+ */
+pub fn local_entry(kind: EntryKind, local: &Path, remote: &Path, metadata: &fs::Metadata) -> TransferEntry {
+    TransferEntry {
+        kind,
+        source: local.to_path_buf(),
+        target: remote.to_path_buf(),
+        size: if kind == EntryKind::File { metadata.len() } else { 0 },
+        mode: local_mode(metadata, kind),
+        mtime: mtime_seconds(metadata),
+    }
+}
+
+fn mtime_seconds(metadata: &fs::Metadata) -> u64 {
+    metadata.modified().ok().and_then(|time| time.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(unix)]
+fn local_mode(metadata: &fs::Metadata, _: EntryKind) -> u32 {
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn local_mode(_: &fs::Metadata, kind: EntryKind) -> u32 {
+    default_mode(kind)
+}
+
+fn default_mode(kind: EntryKind) -> u32 {
+    match kind {
+        EntryKind::Dir => DEFAULT_DIR_MODE,
+        EntryKind::File => DEFAULT_FILE_MODE,
+    }
+}
+
+/**
+ * 列出远程路径(文件或目录)下需要下载的全部条目,目录在其内容之前
+ */
+pub fn plan_download(sftp: &Sftp, remote: &Path, local: &Path) -> Result<Vec<TransferEntry>, String> {
+    let stat = sftp.stat(remote).map_err(|e| format!("读取远程文件 {} 失败: {}", remote.display(), e.message()))?;
+
+    let mut entries: Vec<TransferEntry> = Vec::new();
+    walk_remote(sftp, remote, &stat, local, &mut entries)?;
+
+    Ok(entries)
+}
+
+fn walk_remote(sftp: &Sftp, remote: &Path, stat: &FileStat, local: &Path, entries: &mut Vec<TransferEntry>) -> Result<(), String> {
+    if stat.is_dir() {
+        entries.push(remote_entry(EntryKind::Dir, remote, local, stat));
+
+        let mut children = sftp
+            .readdir(remote)
+            .map_err(|e| format!("读取远程目录 {} 失败: {}", remote.display(), e.message()))?;
+        children.sort_by(|a, b| a.0.cmp(&b.0));
+        for (path, child) in children {
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => continue,
+            };
+            if name == "." || name == ".." {
+                continue;
+            }
+            walk_remote(sftp, &remote_join(remote, &name), &child, &local.join(&name), entries)?;
+        }
+    } else if stat.is_file() {
+        entries.push(remote_entry(EntryKind::File, remote, local, stat));
+    }
+
+    Ok(())
+}
+
+fn remote_entry(kind: EntryKind, remote: &Path, local: &Path, stat: &FileStat) -> TransferEntry {
+    TransferEntry {
+        kind,
+        source: remote.to_path_buf(),
+        target: local.to_path_buf(),
+        size: if kind == EntryKind::File { stat.size.unwrap_or(0) } else { 0 },
+        mode: stat.perm.map(|perm| perm & 0o7777).unwrap_or_else(|| default_mode(kind)),
+        mtime: stat.mtime.unwrap_or(0),
+    }
+}
+
+/**
+ * 按计划上传,resume 为 true 时从上次中断处继续(见 resume_offset)
+ */
+pub fn upload(sftp: &Sftp, entries: &[TransferEntry], resume: bool, progress: &Progress) -> Result<(), String> {
+    for entry in entries {
+        progress.start(entry);
+
+        match entry.kind {
+            EntryKind::Dir => {
+                if !remote_is_dir(sftp, &entry.target) {
+                    sftp.mkdir(&entry.target, entry.mode as i32)
+                        .map_err(|e| format!("创建远程目录 {} 失败: {}", entry.target.display(), e.message()))?;
+                }
+            }
+            EntryKind::File => {
+                upload_file(sftp, entry, resume, progress)
+                    .map_err(|e| format!("上传 {} 失败: {}", entry.source.display(), e))?;
+                set_remote_attributes(sftp, entry)?;
+            }
+        }
+    }
+
+    // 目录的修改时间在写入内容后才能保持,最后由深到浅设置
+    for entry in entries.iter().rev().filter(|entry| entry.kind == EntryKind::Dir) {
+        set_remote_attributes(sftp, entry)?;
+    }
+
+    Ok(())
+}
+
+fn upload_file(sftp: &Sftp, entry: &TransferEntry, resume: bool, progress: &Progress) -> io::Result<()> {
+    if resume {
+        if let Ok(stat) = sftp.stat(&entry.target) {
+            if stat.is_file() && is_complete(stat.size.unwrap_or(0), stat.mtime.unwrap_or(0), entry) {
+                progress.inc(entry.size);
+                return Ok(());
+            }
+        }
+    }
+
+    let part = part_path(&entry.target);
+    let stamp = stamp_path(&entry.target);
+    let offset = match sftp.stat(&part) {
+        Ok(stat) if resume && stat.is_file() => {
+            let recorded = sftp.open(&stamp).ok().and_then(|mut file| {
+                let mut content = String::new();
+                file.read_to_string(&mut content).ok().map(|_| content)
+            });
+            resume_offset(stat.size.unwrap_or(0), recorded.as_deref(), entry)
+        }
+        _ => 0,
+    };
+    if offset == 0 {
+        sftp.create(&stamp)?.write_all(source_stamp(entry).as_bytes())?;
+    }
+
+    {
+        let flags = if offset > 0 {
+            OpenFlags::WRITE
+        } else {
+            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE
+        };
+        let mut remote = sftp.open_mode(&part, flags, entry.mode as i32, OpenType::File)?;
+        let mut local = File::open(&entry.source)?;
+
+        remote.seek(SeekFrom::Start(offset))?;
+        local.seek(SeekFrom::Start(offset))?;
+        progress.inc(offset);
+
+        copy_with_progress(&mut local, &mut remote, progress)?;
+    }
+
+    // SFTP v3 的服务器(如 OpenSSH)在目标已存在时拒绝重命名,先删除旧文件再重试
+    if sftp.rename(&part, &entry.target, None).is_err() {
+        let _ = sftp.unlink(&entry.target);
+        sftp.rename(&part, &entry.target, None)?;
+    }
+    let _ = sftp.unlink(&stamp);
+
+    Ok(())
+}
+
+fn set_remote_attributes(sftp: &Sftp, entry: &TransferEntry) -> Result<(), String> {
+    let stat = FileStat {
+        size: None,
+        uid: None,
+        gid: None,
+        perm: Some(entry.mode),
+        atime: Some(entry.mtime),
+        mtime: Some(entry.mtime),
+    };
+
+    sftp.setstat(&entry.target, stat)
+        .map_err(|e| format!("设置远程文件 {} 的属性失败: {}", entry.target.display(), e.message()))
+}
+
+/**
+ * 按计划下载,resume 为 true 时从上次中断处继续(见 resume_offset)
+ */
+pub fn download(sftp: &Sftp, entries: &[TransferEntry], resume: bool, progress: &Progress) -> Result<(), String> {
+    for entry in entries {
+        progress.start(entry);
+
+        match entry.kind {
+            EntryKind::Dir => {
+                fs::create_dir_all(&entry.target)
+                    .map_err(|e| format!("创建本地目录 {} 失败: {}", entry.target.display(), e))?;
+            }
+            EntryKind::File => {
+                download_file(sftp, entry, resume, progress)
+                    .map_err(|e| format!("下载 {} 失败: {}", entry.source.display(), e))?;
+                set_local_attributes(entry)?;
+            }
+        }
+    }
+
+    for entry in entries.iter().rev().filter(|entry| entry.kind == EntryKind::Dir) {
+        set_local_attributes(entry)?;
+    }
+
+    Ok(())
+}
+
+fn download_file(sftp: &Sftp, entry: &TransferEntry, resume: bool, progress: &Progress) -> io::Result<()> {
+    if resume {
+        if let Ok(metadata) = fs::metadata(&entry.target) {
+            if metadata.is_file() && is_complete(metadata.len(), mtime_seconds(&metadata), entry) {
+                progress.inc(entry.size);
+                return Ok(());
+            }
+        }
+    }
+
+    let part = part_path(&entry.target);
+    let stamp = stamp_path(&entry.target);
+    let offset = match fs::metadata(&part) {
+        Ok(metadata) if resume && metadata.is_file() => {
+            resume_offset(metadata.len(), fs::read_to_string(&stamp).ok().as_deref(), entry)
+        }
+        _ => 0,
+    };
+    if offset == 0 {
+        fs::write(&stamp, source_stamp(entry))?;
+    }
+
+    {
+        let mut local = OpenOptions::new().write(true).create(true).truncate(offset == 0).open(&part)?;
+        let mut remote = sftp.open(&entry.source)?;
+
+        local.seek(SeekFrom::Start(offset))?;
+        remote.seek(SeekFrom::Start(offset))?;
+        progress.inc(offset);
+
+        copy_with_progress(&mut remote, &mut local, progress)?;
+    }
+
+    fs::rename(&part, &entry.target)?;
+    let _ = fs::remove_file(&stamp);
+
+    Ok(())
+}
+
+// 目标文件大小与修改时间都与源文件一致视为已传输完成(传输完成后才会同步修改时间)
+fn is_complete(size: u64, mtime: u64, entry: &TransferEntry) -> bool {
+    size == entry.size && mtime == entry.mtime
+}
+
+// 临时文件不大于源文件、且记录的源文件大小与修改时间未变时视为上次中断的传输,从其末尾继续,否则重新传输
+// 已存在的目标文件即使比源文件小也不会被续写,避免旧文件内容与新文件拼接
+fn resume_offset(part_size: u64, recorded: Option<&str>, entry: &TransferEntry) -> u64 {
+    if recorded.map(str::trim) == Some(source_stamp(entry).trim()) && part_size <= entry.size {
+        part_size
+    } else {
+        0
+    }
+}
+
+// 传输中使用的临时文件地址
+fn part_path(target: &Path) -> PathBuf {
+    PathBuf::from(format!("{}{}", target.to_string_lossy(), PART_SUFFIX))
+}
+
+// 记录源文件大小与修改时间的文件地址
+fn stamp_path(target: &Path) -> PathBuf {
+    PathBuf::from(format!("{}{}", target.to_string_lossy(), STAMP_SUFFIX))
+}
+
+fn source_stamp(entry: &TransferEntry) -> String {
+    format!("{} {}\n", entry.size, entry.mtime)
+}
+
+fn set_local_attributes(entry: &TransferEntry) -> Result<(), String> {
+    let error = |e: io::Error| format!("设置本地文件 {} 的属性失败: {}", entry.target.display(), e);
+
+    #[cfg(unix)]
+    fs::set_permissions(&entry.target, fs::Permissions::from_mode(entry.mode)).map_err(error)?;
+
+    let mtime = FileTime::from_system_time(SystemTime::UNIX_EPOCH + Duration::from_secs(entry.mtime));
+    filetime::set_file_times(&entry.target, mtime, mtime).map_err(error)
+}
+
+fn copy_with_progress(reader: &mut impl Read, writer: &mut impl Write, progress: &Progress) -> io::Result<()> {
+    let mut buf = vec![0u8; BUFFER_SIZE];
+
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        writer.write_all(&buf[..n])?;
+        progress.inc(n as u64);
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(size: u64, mtime: u64) -> TransferEntry {
+        TransferEntry {
+            kind: EntryKind::File,
+            source: PathBuf::from("dist/app.js"),
+            target: PathBuf::from("/data/www/app.js"),
+            size,
+            mode: DEFAULT_FILE_MODE,
+            mtime,
+        }
+    }
+
+    #[test]
+    fn resumes_only_from_a_shorter_part_file() {
+        let stamp = source_stamp(&entry(100, 1));
+        assert_eq!(resume_offset(0, Some(&stamp), &entry(100, 1)), 0);
+        assert_eq!(resume_offset(40, Some(&stamp), &entry(100, 1)), 40);
+        assert_eq!(resume_offset(100, Some(&stamp), &entry(100, 1)), 100);
+        // 临时文件比源文件大说明源文件已变化,重新传输
+        assert_eq!(resume_offset(120, Some(&stamp), &entry(100, 1)), 0);
+    }
+
+    #[test]
+    fn stale_part_file_is_not_resumed() {
+        // 源文件大小或修改时间与开始传输时不同
+        assert_eq!(resume_offset(40, Some("100 1\n"), &entry(100, 2)), 0);
+        assert_eq!(resume_offset(40, Some("90 1\n"), &entry(100, 1)), 0);
+        // 没有记录(旧版本留下的临时文件)时无法确认,重新传输
+        assert_eq!(resume_offset(40, None, &entry(100, 1)), 0);
+        assert_eq!(resume_offset(40, Some("100 1"), &entry(100, 1)), 40);
+    }
+
+    #[test]
+    fn target_is_complete_only_when_size_and_mtime_match() {
+        assert!(is_complete(100, 7, &entry(100, 7)));
+        assert!(!is_complete(40, 7, &entry(100, 7)));
+        assert!(!is_complete(100, 8, &entry(100, 7)));
+    }
+
+    #[test]
+    fn part_file_sits_next_to_the_target() {
+        assert_eq!(part_path(Path::new("/data/www/app.js")), PathBuf::from("/data/www/app.js.aspen-part"));
+        assert_eq!(part_path(Path::new("logs/access.log")), PathBuf::from("logs/access.log.aspen-part"));
+        assert_eq!(stamp_path(Path::new("logs/access.log")), PathBuf::from("logs/access.log.aspen-part.src"));
+    }
+}
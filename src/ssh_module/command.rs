@@ -1,158 +1,343 @@
-use std::{
-    io::{stdin, Write},
-    process,
-};
+use std::{env, io::{stdin, stdout, IsTerminal}, path::{Path, PathBuf}};
 use clap::ArgMatches;
 use colored::Colorize;
 use prettytable::{format, row, Table};
 
-#[cfg(any(target_os = "macos", target_os = "linux"))]
 use crate::aspen_module::cli::get_home_dir;
-#[cfg(any(target_os = "macos", target_os = "linux"))]
-use std::{env, io};
+#[cfg(target_os = "macos")]
+use std::io;
 
-#[cfg(target_os = "windows")]
-use std::{net::TcpStream, thread, io::{stdout, Read}, time::Duration};
-#[cfg(target_os = "windows")]
-use ssh2::{PtyModes, Session, FLUSH_ALL};
-#[cfg(target_os = "windows")]
-use crossterm::event::{read, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crate::ssh_module::shell::open_shell;
 
 use crate::aspen_module::config::{get_aspen_config, write_aspen_config};
-use crate::ssh_module::config::{get_config, ServerConfig};
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::config::{expand_source, get_config, is_glob, read_server_config, ServerConfig};
+use crate::ssh_module::guard::{confirm_dangerous, SessionTitle};
+#[cfg(target_os = "macos")]
+use crate::ssh_module::guard::print_banner;
+#[cfg(target_os = "macos")]
+use crate::ssh_module::history::record_usage;
+use crate::ssh_module::picker::pick_server_tui;
+use crate::ssh_module::selector::Selector;
+use crate::ssh_module::target::resolve_target;
+use crate::ssh_module::known_hosts::{
+    append_host_key, check_host_key, fingerprint, key_type_name, known_hosts_path,
+    read_known_hosts, remove_host_keys, HostKeyStatus,
+};
+use crate::ssh_module::session::handshake_session;
+#[cfg(target_os = "macos")]
+use crate::ssh_module::script::login_command;
+#[cfg(target_os = "macos")]
+use crate::ssh_module::known_hosts::verify_host_key;
 
 
 // ssh 命令实现
-pub fn impl_ssh_action(matches: &ArgMatches) {
+pub fn impl_ssh_action(matches: &ArgMatches) -> Result<()> {
+    let filter = Selector::from_filter_args(matches)?;
+    let sure = matches.get_flag("yes-i-am-sure");
+
     if let Some(index) = matches.get_one::<String>("index") {
-        ssh_index_action(index.clone());
+        ssh_index_action(index.clone(), &filter, sure)
     } else {
-        ssh_none_index_action();
+        ssh_none_index_action(&filter, sure)
     }
 }
 
 /**
- * 实现设置服务器配置文件地址
+ * path 命令实现: 管理服务器配置文件列表
  */
-pub fn import_set_servers_path_action(matches: &ArgMatches) {
-    if let Some(path) = matches.get_one::<String>("path") {
-        let mut aspen_config = get_aspen_config();
-        aspen_config.service_config_path = path.clone();
-
-        match write_aspen_config(&aspen_config) {
-            Ok(_) => {
-                println!("\n[Aspen Success] ==> {}\n", "设置成功".green());
-                process::exit(0);
-            }
-            Err(_) => {
-                println!("\n[Aspen Error] ==> {}\n", "设置失败".red());
-                process::exit(0);
-            }
+pub fn impl_path_action(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("add", sub_matches)) => path_add_action(sub_matches),
+        Some(("rm", sub_matches)) => path_rm_action(sub_matches),
+        Some(("list", _)) => path_list_action(),
+        _ => Err(AspenError::Usage("非法指令".to_string())),
+    }
+}
+
+// 添加配置文件,默认追加到末尾(优先级最高),相对地址转为绝对地址
+fn path_add_action(matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<String>("path").unwrap();
+    let path = if path.starts_with('~') || Path::new(path).is_absolute() {
+        path.clone()
+    } else {
+        match env::current_dir() {
+            Ok(dir) => dir.join(path).to_string_lossy().to_string(),
+            Err(_) => path.clone(),
         }
+    };
+
+    let mut aspen_config = get_aspen_config()?;
+    if aspen_config.service_config_paths.contains(&path) {
+        return Err(AspenError::Usage(format!("{} 已在配置文件列表中!", path)));
+    }
+
+    if matches.get_flag("first") {
+        aspen_config.service_config_paths.insert(0, path.clone());
+    } else {
+        aspen_config.service_config_paths.push(path.clone());
     }
+    write_aspen_config(&aspen_config)?;
+
+    println!("\n[Aspen Success] ==> 已添加配置文件 {}\n", path.green());
+    Ok(())
+}
+
+// 按 地址 或 序号 移除配置文件(不删除文件本身)
+fn path_rm_action(matches: &ArgMatches) -> Result<()> {
+    let target = matches.get_one::<String>("path").unwrap();
+    let mut aspen_config = get_aspen_config()?;
+    let paths = &aspen_config.service_config_paths;
+
+    let index = match paths.iter().position(|path| path == target) {
+        Some(index) => index,
+        None => match target.parse::<usize>() {
+            Ok(index) if index >= 1 && index <= paths.len() => index - 1,
+            _ => return Err(AspenError::NotFound(format!("配置文件列表中没有 {}!", target))),
+        },
+    };
+
+    let path = aspen_config.service_config_paths.remove(index);
+    write_aspen_config(&aspen_config)?;
+
+    println!("\n[Aspen Success] ==> 已移除配置文件 {}\n", path.green());
+    Ok(())
+}
+
+// 列出配置文件,靠后的优先级更高
+fn path_list_action() -> Result<()> {
+    let aspen_config = get_aspen_config()?;
+    let primary = aspen_config.service_config_paths.iter().rev().find(|path| !is_glob(path)).cloned();
+
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(row!["ID", "Path", "Files", "Servers", "Note"]);
+
+    for (index, path) in aspen_config.service_config_paths.iter().enumerate() {
+        let files: Vec<PathBuf> = expand_source(path).into_iter().filter(|file| file.exists()).collect();
+        let servers: usize = files
+            .iter()
+            .map(|file| read_server_config(file).map(|configs| configs.len()).unwrap_or(0))
+            .sum();
+
+        let mut notes: Vec<String> = Vec::new();
+        if !is_glob(path) && files.is_empty() {
+            notes.push("文件不存在".red().to_string());
+        }
+        if primary.as_ref() == Some(path) {
+            notes.push("新增服务器写入此文件".green().to_string());
+        }
+
+        table.add_row(row![index + 1, path, files.len(), servers, notes.join(", ")]);
+    }
+
+    println!("\n[Aspen Success] ==> 服务器配置文件(标题相同时靠后的文件优先):");
+    table.printstd();
+    Ok(())
 }
 
 /**
- * 实现获取服务器配置文件地址
+ * known-hosts 命令实现
  */
-pub fn import_get_servers_path_action(_: &ArgMatches) {
-    let aspen_config = get_aspen_config();
+pub fn impl_known_hosts_action(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", sub_matches)) => known_hosts_list_action(sub_matches),
+        Some(("accept", sub_matches)) => known_hosts_accept_action(sub_matches),
+        Some(("rm", sub_matches)) => known_hosts_rm_action(sub_matches),
+        _ => Err(AspenError::Usage("非法指令".to_string())),
+    }
+}
+
+// 查看 known_hosts 中的记录,指定筛选条件时只保留适用于筛选出的服务器的记录
+fn known_hosts_list_action(matches: &ArgMatches) -> Result<()> {
+    let filter = Selector::from_filter_args(matches)?;
+    let mut entries = read_known_hosts();
+    if !filter.is_empty() {
+        let servers: Vec<ServerConfig> = get_config()?.into_iter().filter(|config| filter.matches(config)).collect();
+        entries.retain(|entry| servers.iter().any(|config| entry.matches(&config.host, config.port)));
+    }
 
-    println!("\n[Aspen Success] ==> Servers Path: {}\n", aspen_config.service_config_path.green());
-    process::exit(0);
+    let mut table = Table::new();
+    table.set_titles(row!["Line", "Hosts", "Key Type", "Fingerprint", "Marker"]);
+    for entry in &entries {
+        // 哈希形式的主机名无法还原,只做标记
+        let hosts = if entry.hosts.starts_with("|1|") { "(hashed)".to_string() } else { entry.hosts.clone() };
+        table.add_row(row![
+            entry.line, hosts, entry.key_type, entry.fingerprint(),
+            entry.marker.clone().unwrap_or_default()
+        ]);
+    }
+
+    println!("\n[Aspen Success] ==> {}: {}\n", "known_hosts".green(), known_hosts_path()?.display());
+    table.printstd();
+    Ok(())
+}
+
+// 获取服务器当前的公钥并写入 known_hosts(替换旧记录)
+fn known_hosts_accept_action(matches: &ArgMatches) -> Result<()> {
+    let target = matches.get_one::<String>("target").unwrap();
+    let config_lines = get_config()?;
+    let config = &resolve_target(&config_lines, target, &Selector::from_filter_args(matches)?)?;
+
+    let sess = handshake_session(config)?;
+    let blob = match sess.host_key() {
+        Some((blob, _)) => blob.to_vec(),
+        None => return Err(AspenError::HostKey("无法获取主机公钥!".to_string())),
+    };
+
+    let fingerprint = fingerprint(&blob);
+    let type_name = key_type_name(&blob).unwrap_or("unknown");
+    if check_host_key(config, &blob) == HostKeyStatus::Match {
+        println!("\n[Aspen Success] ==> 【 {} 】已在 known_hosts 中 ({} {})\n", config.title.green(), type_name, fingerprint);
+        return Ok(());
+    }
+
+    println!("\n[Aspen Waiting] ==> 【 {} 】{} 指纹: {}", config.title.green(), type_name, fingerprint.yellow());
+    if !matches.get_flag("yes") {
+        if !stdin().is_terminal() {
+            return Err(AspenError::Usage("非交互环境下请确认指纹后使用 --yes 信任该主机".to_string()));
+        }
+
+        println!("请确认指纹无误后输入 {} 信任该主机:", "yes".green());
+        let mut answer = String::new();
+        // 读到 EOF 时 answer 为空,视为取消
+        if stdin().read_line(&mut answer).is_err() || !answer.trim().eq_ignore_ascii_case("yes") {
+            return Err(AspenError::Cancelled);
+        }
+    }
+
+    let removed = remove_host_keys(&config.host, config.port)?;
+    append_host_key(&config.host, config.port, &blob)?;
+
+    println!("\n[Aspen Success] ==> 已信任【 {} 】(替换旧记录 {} 条)\n", config.title.green(), removed);
+    Ok(())
+}
+
+// 删除指定主机的记录
+fn known_hosts_rm_action(matches: &ArgMatches) -> Result<()> {
+    let host = matches.get_one::<String>("host").unwrap();
+    let port: u32 = matches
+        .get_one::<String>("port")
+        .unwrap()
+        .trim()
+        .parse()
+        .map_err(|_| AspenError::Usage("端口只能输入合法的整数!".to_string()))?;
+
+    let removed = remove_host_keys(host, port)?;
+    if removed == 0 {
+        return Err(AspenError::NotFound(format!("known_hosts 中没有 {} 的记录!", host)));
+    }
+
+    println!("\n[Aspen Success] ==> 已删除 {} 的记录 {} 条\n", host.green(), removed);
+    Ok(())
 }
 
 /**
  * 实现 ssh 命令,没有输入index
  */
-fn ssh_none_index_action() {
-    let config_lines = get_config();
-
-    print_services_table(&config_lines);
-
-    if !&config_lines.is_empty() {
-        println!("请输入 {} 选择要登录的服务器:", "序号".green());
-        loop {
-            let mut guess = String::new();
-            stdin().read_line(&mut guess).expect("读取输入错误");
-
-            let guess: i32 = match guess.trim().parse() {
-                Ok(num) => {
-                    if num < 1 {
-                        eprintln!("\n[Aspen Error] => {} 请重新输入服务器 {}:", "序号必须大于 0 !".red(), "序号".green());
-                        continue;
-                    }
-
-                    if num > config_lines.len() as i32 {
-                        eprintln!("\n[Aspen Error] => {} 请重新输入服务器 {}:", "您输入的序号超过了配置项的数量!".red(), "序号".green());
-                        continue;
-                    }
-
-                    num
-                }
-                Err(_) => {
-                    eprintln!("\n[Aspen Error] => {} 请重新输入服务器 {}:", "序号只能输入合法的整数!".red(), "序号".green());
-                    continue;
-                }
-            };
+fn ssh_none_index_action(filter: &Selector, sure: bool) -> Result<()> {
+    let config_lines = get_config()?;
 
-            let index = guess - 1;
-            let config = config_lines.get(index as usize).unwrap();
+    if config_lines.is_empty() {
+        print_services_table(&config_lines);
+        return Ok(());
+    }
 
-            ssh_login(config);
-            break;
-        }
+    // 只在满足筛选条件的服务器中选择
+    let config_lines: Vec<ServerConfig> = config_lines.into_iter().filter(|config| filter.matches(config)).collect();
+    if config_lines.is_empty() {
+        return Err(AspenError::NotFound("没有满足筛选条件的服务器!".to_string()));
     }
+
+    let index = pick_server(&config_lines, None)?;
+    ssh_login(&config_lines[index], sure)
 }
 
 /**
- * 实现 ssh 命令输入了 index 的情况
+ * 选择服务器,返回选中服务器的下标
+ * 终端环境下使用全屏选择界面,否则打印列表并等待输入序号
  */
-fn ssh_index_action(index: String) {
-    let key: i32 = match index.trim().parse() {
-        Ok(num) => {
-            if num < 1 {
-                eprintln!("\n[Aspen Error] => {} 请重新输入服务器 {}:", "序号必须大于 0 !".red(), "序号".green());
-                process::exit(0);
-            }
+pub fn pick_server(lines: &[ServerConfig], hint: Option<&str>) -> Result<usize> {
+    // 非交互环境(脚本、管道)无法选择,直接列出候选并返回错误,避免等待输入
+    if !stdin().is_terminal() {
+        return Err(unresolved_error(lines, hint));
+    }
 
-            num
-        }
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {} 请重新输入服务器 {}:", "序号只能输入合法的整数!".red(), "序号".green());
-            process::exit(0);
+    if stdout().is_terminal() {
+        match pick_server_tui(lines, hint) {
+            Ok(Some(index)) => return Ok(index),
+            Ok(None) => return Err(AspenError::Cancelled),
+            // 终端不支持全屏界面时退回序号输入
+            Err(_) => {}
         }
-    };
+    }
 
-    let config_lines = get_config();
-    match config_lines.get((key - 1) as usize) {
-        Some(config) => {
-            // 清屏
-            clear_terminal();
+    print_services_table(lines);
 
-            ssh_login(config);
-        }
-        None => {
-            eprintln!("\n[Aspen Error] => {} \n", "您输入的序号超过了配置项的数量!".red());
-            process::exit(0);
+    if let Some(hint) = hint {
+        println!("\n[Aspen Waiting] ==> {}", hint);
+    }
+
+    println!("请输入 {} 选择要登录的服务器:", "序号".green());
+    loop {
+        let mut guess = String::new();
+        match stdin().read_line(&mut guess) {
+            Ok(0) | Err(_) => return Err(unresolved_error(lines, hint)),
+            Ok(_) => {}
         }
+
+        let guess: usize = match guess.trim().parse::<i64>() {
+            Ok(num) => {
+                if num < 1 {
+                    eprintln!("\n[Aspen Error] => {} 请重新输入服务器 {}:", "序号必须大于 0 !".red(), "序号".green());
+                    continue;
+                }
+
+                if num > lines.len() as i64 {
+                    eprintln!("\n[Aspen Error] => {} 请重新输入服务器 {}:", "您输入的序号超过了配置项的数量!".red(), "序号".green());
+                    continue;
+                }
+
+                num as usize
+            }
+            Err(_) => {
+                eprintln!("\n[Aspen Error] => {} 请重新输入服务器 {}:", "序号只能输入合法的整数!".red(), "序号".green());
+                continue;
+            }
+        };
+
+        return Ok(guess - 1);
     }
 }
 
+// 无法交互选择时的错误,列出全部候选服务器
+fn unresolved_error(lines: &[ServerConfig], hint: Option<&str>) -> AspenError {
+    let titles: Vec<&str> = lines.iter().map(|line| line.title.as_str()).collect();
+
+    AspenError::Usage(format!(
+        "{},请指定唯一的 序号、标题 或 别名,候选: {}",
+        hint.unwrap_or("未指定服务器"), titles.join(", ")
+    ))
+}
+
 /**
- * 查看已配置服务器列表
+ * 实现 ssh 命令输入了 序号、标题、别名 或 关键字 的情况
  */
-pub fn impl_servers_table_action(_: &ArgMatches) {
-    let config_lines = get_config();
+fn ssh_index_action(query: String, filter: &Selector, sure: bool) -> Result<()> {
+    let config_lines = get_config()?;
+    let config = resolve_target(&config_lines, &query, filter)?;
+
+    // 清屏
+    clear_terminal();
 
-    print_services_table(&config_lines);
+    ssh_login(&config, sure)
 }
 
-// 打印服务器列表
-fn print_services_table(lines: &Vec<ServerConfig>) {
-    // 创建表格
-    let mut table = Table::new();
-    let format = format::FormatBuilder::new()
+/**
+ * 工具箱统一的表格样式
+ */
+pub fn table_format() -> format::TableFormat {
+    format::FormatBuilder::new()
         .column_separator('|')
         .borders('|')
         .separators(&[
@@ -162,12 +347,17 @@ fn print_services_table(lines: &Vec<ServerConfig>) {
             format::LinePosition::Bottom
         ], format::LineSeparator::new('-', '+', '+', '+'))
         .padding(2, 2)
-        .build();
+        .build()
+}
 
-    table.set_format(format);
+// 打印服务器列表
+fn print_services_table(lines: &[ServerConfig]) {
+    // 创建表格
+    let mut table = Table::new();
+    table.set_format(table_format());
     // 设置标题
     table.set_titles(row![
-        "ID","Title","Host","Port","Server Type","Username","Remark"
+        "ID","Title","Host","Port","Server Type","Username","Remark","Source"
     ]);
 
     // 添加行
@@ -175,7 +365,7 @@ fn print_services_table(lines: &Vec<ServerConfig>) {
         for (index, line) in lines.iter().enumerate() {
             table.add_row(row![
                 (index+1),line.title, line.host, line.port,
-                line.category,line.username,line.remark
+                line.category,line.username,line.remark,display_path(&line.source)
             ]);
         }
     }
@@ -188,244 +378,80 @@ fn print_services_table(lines: &Vec<ServerConfig>) {
 }
 
 /**
- * 重点中的重点!! 实现ssh链接服务器的全部功能
+ * 显示用的文件地址,主目录替换为 ~
  */
-fn ssh_login(config: &ServerConfig) {
-    // macOS 平台下编译
-    #[cfg(any(target_os = "macos", target_os = "linux"))]
-    {
-        ssh_login_macos_and_linux(&config);
-    }
-
-    // windows平台下处理
-    #[cfg(target_os = "windows")]
-    {
-        ssh_login_windows(&config)
+pub fn display_path(path: &Path) -> String {
+    match get_home_dir().ok().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
     }
 }
 
-// windows 系统中,目前使用ssh2来处理远程登录问题
-#[cfg(target_os = "windows")]
-fn ssh_login_windows(config: &ServerConfig) {
-    let connect = format!("{}:{}", config.host.clone(), config.port.clone());
-    let username = config.username.clone();
-    let password = config.password.clone();
-    let title = config.title.clone();
-
-    println!("\n[Aspen Waiting] ==> 正在登录【 {} 】，请稍等...", config.title.clone().green());
-
-    let mut sess = match Session::new() {
-        Ok(session) => session,
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {}\n", "与主机进行 Session 链接失败！".red());
-            process::exit(0);
-        }
-    };
-
-    match TcpStream::connect(connect) {
-        Ok(tcp) => {
-            sess.set_tcp_stream(tcp);
-        }
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {}\n", "链接超时,请检查您的网络是否通畅或者您的Host信息是否正确".red());
-            process::exit(0);
-        }
-    }
-
-    match sess.handshake() {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {}\n", "与主机进行传输层协议协商失败!".red());
-            process::exit(0);
-        }
-    }
-
-    match sess.userauth_password(&username, &password) {
-        Ok(_) => (),
-        Err(e) => {
-            eprintln!("\n[Aspen Error] => {}\n", e.message().red());
-            process::exit(0);
-        }
-    }
-
-    let mut pty_modes = PtyModes::new();
-    pty_modes.set_u32(ssh2::PtyModeOpcode::TTY_OP_OSPEED, 115200);
-    pty_modes.set_u32(ssh2::PtyModeOpcode::TTY_OP_ISPEED, 115200);
-
-    let mut channel = match sess.channel_session() {
-        Ok(channel) => channel,
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {}\n", "与主机会话通道建立失败".red());
-            process::exit(0);
-        }
-    };
-
-    match channel.request_pty("xterm-256color", Some(pty_modes), Some((80, 24, 0, 0))) {
-        Ok(_) => {}
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {}\n", "与主机会话通道请求PTY失败！".red());
-            process::exit(0);
-        }
-    }
-
-    match channel.shell() {
-        Ok(_) => {}
-        Err(_) => {
-            eprintln!("\n[Aspen Error] => {}\n", "启动SSH失败！".red());
-            process::exit(0);
-        }
-    }
-
-    match channel.handle_extended_data(ssh2::ExtendedData::Merge) {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("\n[Aspen Error] => {}\n", e.message().red());
-            process::exit(0);
+/**
+ * 重点中的重点!! 实现ssh链接服务器的全部功能
+ */
+fn ssh_login(config: &ServerConfig, sure: bool) -> Result<()> {
+    // 敏感服务器先确认,会话期间终端标题显示环境
+    confirm_dangerous(config, "登录", sure)?;
+    let _title = SessionTitle::set(config);
+
+    // macOS 平台下沿用 expect 脚本,经跳板机链接时系统 ssh 无法复用配置,改用ssh2实现
+    // 系统 ssh 接管终端后无法固定横幅,只在登录前清屏并输出一次
+    #[cfg(target_os = "macos")]
+    if config.proxy_jump.is_none() {
+        if config.danger {
+            clear_terminal();
         }
+        print_banner(config);
+        return ssh_login_macos(config);
     }
 
-    // 阻塞模式最后设置,避免实例化操作链接会阻塞
-    sess.set_blocking(false);
-
-    let mut ssh_stdin = channel.stream(0);
-
-    let stdin_thread = thread::spawn(move || {
-        loop {
-            let mut buf = [0; 4096];
-            let data = {
-                match read().unwrap() {
-                    Event::Key(e) if matches!(e.kind, KeyEventKind::Press) => match e.code {
-                        KeyCode::Char(c) => match e.modifiers {
-                            KeyModifiers::CONTROL => {
-                                buf[0] = c as u8 - 96;
-                                &buf[..1]
-                            }
-                            KeyModifiers::NONE => c.encode_utf8(&mut buf).as_bytes(),
-                            _ => c.encode_utf8(&mut buf).as_bytes(),
-                        },
-                        _ => match e.code {
-                            KeyCode::Enter => "\n",
-                            KeyCode::Backspace => "\x08",
-                            KeyCode::Tab => "\x09",
-                            KeyCode::Esc => "\x1b",
-                            KeyCode::Home => "\x1b[H",
-                            KeyCode::End => "\x1b[F",
-                            KeyCode::Insert => "\x1b\x5b\x32\x7e",
-                            KeyCode::Delete => "\x1b\x5b\x33\x7e",
-                            KeyCode::Left => "\x1b[D",
-                            KeyCode::Up => "\x1b[A",
-                            KeyCode::Right => "\x1b[C",
-                            KeyCode::Down => "\x1b[B",
-                            _ => todo!(),
-                        }.as_bytes(),
-                    },
-                    _ => continue,
-                }
-            };
-
-            ssh_stdin.write_all(&data).unwrap();
-        };
-    });
-
-    let stdout_thread = thread::spawn(move || {
-        println!("\n {} \n", "Login Successful!!!".green());
-        loop {
-            let mut buf = [0; 4096];
-            match channel.read(&mut buf) {
-                Ok(c) if c > 0 => {
-                    print!("{}", std::str::from_utf8(&buf[..c]).unwrap());
-                    channel.stream(FLUSH_ALL).flush().unwrap();
-                    stdout().flush().unwrap();
-                }
-                Ok(0) => break,
-                _ => thread::sleep(Duration::from_millis(1)),
-            };
-        }
-
-        let exit_status = match channel.exit_status() {
-            Ok(n) => n,
-            Err(_) => {
-                eprintln!("\n[Aspen Error] => {}\n", "主机会话状态丢失！".red());
-                channel.close().unwrap();
-                process::exit(0);
-            }
-        };
-
-        if exit_status == 0 {
-            println!("\n[Aspen Success] ==> 您已退出【 {} 】\n", title.green());
-            channel.close().unwrap();
-            process::exit(0);
-        }
-    });
-
-    stdin_thread.join().unwrap();
-    stdout_thread.join().unwrap();
+    // linux、windows 平台下使用ssh2原生实现,环境横幅固定在终端第一行
+    open_shell(config)
 }
 
-// macos linux 系统中,ssh登录的实现(采用脚本命令去处理,解决ssh2中命令tab和vim编码问题)
-#[cfg(any(target_os = "macos", target_os = "linux"))]
-fn ssh_login_macos_and_linux(config: &ServerConfig) {
+// macos 系统中,ssh登录的实现(采用脚本命令去处理)
+#[cfg(target_os = "macos")]
+fn ssh_login_macos(config: &ServerConfig) -> Result<()> {
+    let config = &config.with_resolved_secrets()?;
     println!("\n[Aspen Waiting] ==> 正在登录【 {} 】，请稍等...", config.title.clone().green());
 
+    // 系统 ssh 启动前先校验主机公钥
+    verify_host_key(&handshake_session(config)?, config)?;
+
     let dir = env!("CARGO_PKG_NAME");
-    let controller_path = format!("{}/{}/shell/controller.sh", get_home_dir().to_str().unwrap().to_string(), dir.to_string());
-
-    // 参数列表 (名称,IP,Port,用户名,密码)
-    let args = vec![
-        "-e".to_string(),
-        controller_path,
-        config.title.clone(),
-        config.host.clone(),
-        config.port.clone().to_string(),
-        config.username.clone(),
-        config.password.to_string(),
-    ];
-
-    // 执行用户输入的命令
-    let mut child = process::Command::new("sh")
-        .args(&args)
-        .spawn()
-        .expect("执行命令失败");
+    let controller_path = format!("{}/{}/shell/controller.sh", get_home_dir()?.to_str().unwrap(), dir);
 
-    // 从子进程的 stdin 获取一个写入器
-    if let Some(mut stdin) = child.stdin.take() {
-        // 向子进程发送输入
-        writeln!(stdin, "echo 'Hello, World!'").unwrap();
-    }
+    // 执行登录脚本,密码通过环境变量传递,不出现在命令行参数中
+    let mut child = login_command(config, &controller_path)
+        .spawn()
+        .map_err(|_| AspenError::Io("执行登录脚本失败！".to_string()))?;
 
     // 从子进程的 stdout 获取一个读取器
     if let Some(mut stdout) = child.stdout.take() {
         // 从子进程读取输出
-        match io::copy(&mut stdout, &mut io::stdout()) {
-            Ok(_) => (),
-            Err(_) => {
-                eprintln!("\n[Aspen Error] => {}\n", "从子进程读取输出失败！".red());
-                process::exit(0);
-            }
-        }
+        io::copy(&mut stdout, &mut io::stdout())
+            .map_err(|_| AspenError::Io("从子进程读取输出失败！".to_string()))?;
     }
 
     // 从子进程的 stderr 获取一个读取器
     if let Some(mut stderr) = child.stderr.take() {
         // 从子进程读取错误信息
-        match io::copy(&mut stderr, &mut io::stderr()) {
-            Ok(_) => (),
-            Err(_) => {
-                eprintln!("\n[Aspen Error] => {}\n", "从子进程读取错误信息失败！".red());
-                process::exit(0);
-            }
-        }
+        io::copy(&mut stderr, &mut io::stderr())
+            .map_err(|_| AspenError::Io("从子进程读取错误信息失败！".to_string()))?;
     }
 
-    // 等待子进程执行完毕
-    child.wait().unwrap();
+    // 等待子进程执行完毕,登录脚本正常退出后才记录使用时间
+    if child.wait().is_ok_and(|status| status.success()) {
+        record_usage(&config.title);
+    }
 
     println!("\n[Aspen Success] ==> 您已退出【 {} 】\n", config.title.green());
-    process::exit(0);
+    Ok(())
 }
 
 //清屏
-fn clear_terminal() {
+pub fn clear_terminal() {
     print!("\x1b[2J");
     print!("\x1b[H");
 }
\ No newline at end of file
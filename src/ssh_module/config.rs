@@ -1,9 +1,13 @@
-use std::{fs::File, io::Read, path::Path, process};
+use std::{collections::BTreeMap, fs::{self, File}, io::{stdin, stdout, IsTerminal, Read, Write}, path::Path};
 use std::path::PathBuf;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table};
 
+use crate::aspen_module::cli::get_home_dir;
 use crate::aspen_module::config::{get_aspen_config};
+use crate::aspen_module::error::{AspenError, Result};
+use crate::vault_module::store::{resolve_secret, Vault};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServerConfig {
@@ -12,59 +16,942 @@ pub struct ServerConfig {
     pub host: String,
     pub port: u32,
     pub username: String,
+    #[serde(default)]
     pub password: String,
     pub remark: String,
+    // 私钥文件地址,支持 ~ 开头
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+    // 私钥的密码
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    // 是否使用 ssh-agent 认证
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub use_agent: bool,
+    // 认证方式的尝试顺序,为空时按 agent -> key -> password 的顺序
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub auth_order: Vec<AuthMethod>,
+    // 别名,可代替标题作为链接目标
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    // 标签,可用于 exec-all 等命令批量选择服务器
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // 键值标签,如 env=prod、team=payments,可在选择表达式中按 键=值 筛选
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    // 敏感服务器(如生产环境),登录、执行命令、传输文件、端口转发前需输入标题确认,会话期间显示环境横幅与终端标题
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub danger: bool,
+    // 跳板机,填写另一台服务器的标题或别名,跳板机自身也可以再配置跳板机
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_jump: Option<String>,
+    // 固定的主机公钥指纹(SHA256:xxx),设置后不再对照 known_hosts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_key_fingerprint: Option<String>,
+    // 本地端口转发(-L),由 tunnel 命令打开
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forwards: Vec<ForwardConfig>,
+    // 远程端口转发(-R),由 rtunnel 命令打开
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remote_forwards: Vec<RemoteForwardConfig>,
+    // 未识别的字段,原样保留,写回配置文件时不丢失
+    #[serde(flatten, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, serde_json::Value>,
+    // 服务器所在的配置文件,读取时填写,不写入文件
+    #[serde(skip)]
+    pub source: PathBuf,
 }
 
 /**
- * 获取配置文件中的服务器数据
+ * 本地端口转发: 本地 local_bind 收到的链接经服务器转发到 remote_host:remote_port
  */
-pub fn get_config() -> Vec<ServerConfig> {
-    let aspen_config = get_aspen_config();
-    let file_path = aspen_config.service_config_path;
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ForwardConfig {
+    // 名称,tunnel 命令可按名称只打开其中一条
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    // 本地监听地址,如 "127.0.0.1:15432",只写端口时监听 127.0.0.1
+    pub local_bind: String,
+    pub remote_host: String,
+    pub remote_port: u16,
+}
 
-    if file_path.is_empty() {
-        eprintln!("\n [Aspen Error] => {}\n", "💔 您的配置文件地址尚未设置,请先执行命令 set-path 设置! ".red());
-        process::exit(0);
+impl ForwardConfig {
+    /**
+     * 本地监听的 地址:端口
+     */
+    pub fn bind_address(&self) -> String {
+        bind_address(&self.local_bind)
     }
+}
 
-    let file = PathBuf::from(file_path.clone());
+/**
+ * 远程端口转发: 服务器上 remote_bind 收到的链接转发到本地 local_address
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteForwardConfig {
+    // 名称,rtunnel 命令可按名称只打开其中一条
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    // 服务器上的监听地址,如 "8080" 或 "0.0.0.0:8080"(需服务器开启 GatewayPorts)
+    pub remote_bind: String,
+    // 本地被转发的地址,如 "127.0.0.1:3000",只写端口时为 127.0.0.1
+    pub local_address: String,
+}
 
-    // 读取服务器配置数据列表
-    if Path::new(&file).exists() {
-        let configs = read_server_config(&file).unwrap();
-        configs
+impl RemoteForwardConfig {
+    /**
+     * 解析 -R 参数: [监听地址:]端口:本地地址:本地端口
+     */
+    pub fn parse(value: &str) -> Option<RemoteForwardConfig> {
+        let parts: Vec<&str> = value.split(':').collect();
+        let (remote_bind, local_address) = match parts.len() {
+            3 => (parts[0].to_string(), format!("{}:{}", parts[1], parts[2])),
+            4 => (format!("{}:{}", parts[0], parts[1]), format!("{}:{}", parts[2], parts[3])),
+            _ => return None,
+        };
+
+        let forward = RemoteForwardConfig { name: String::new(), remote_bind, local_address };
+        forward.remote_listen().map(|_| forward)
+    }
+
+    /**
+     * 服务器上的监听地址与端口,未指定地址时由服务器决定(通常为 localhost)
+     */
+    pub fn remote_listen(&self) -> Option<(Option<String>, u16)> {
+        match self.remote_bind.trim().rsplit_once(':') {
+            Some((host, port)) => port.parse::<u16>().ok().map(|port| (Some(host.to_string()), port)),
+            None => self.remote_bind.trim().parse::<u16>().ok().map(|port| (None, port)),
+        }
+    }
+
+    /**
+     * 本地被转发的 地址:端口
+     */
+    pub fn local_target(&self) -> String {
+        bind_address(&self.local_address)
+    }
+}
+
+/**
+ * 只写端口时补全为 127.0.0.1:端口
+ */
+pub fn bind_address(value: &str) -> String {
+    let value = value.trim();
+    if value.parse::<u16>().is_ok() {
+        format!("127.0.0.1:{}", value)
     } else {
-        eprintln!("\n [Aspen Error] => {}\n", format!("💔 您的配置文件({})不存在 ", file_path).red());
-        process::exit(0);
+        value.to_string()
     }
 }
 
-fn read_server_config(file_path: &PathBuf) -> Result<Vec<ServerConfig>, Box<dyn std::error::Error>> {
-    if let Ok(mut file) = File::open(&file_path) {
-        // 读取文件内容
-        let mut contents = String::new();
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMethod {
+    Agent,
+    Key,
+    Password,
+}
 
-        match file.read_to_string(&mut contents) {
-            Ok(_) => {
-                // 使用 serde_json 解析 JSON
-                let config: Vec<ServerConfig> = match serde_json::from_str(&contents) {
-                    Ok(str) => str,
-                    _ => {
-                        eprintln!("\n[Aspen Error] => {} \n", "解析配置json文件失败,请检查您的配置文件内容是否有误!".red());
-                        process::exit(0);
-                    }
-                };
+impl AuthMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuthMethod::Agent => "agent",
+            AuthMethod::Key => "key",
+            AuthMethod::Password => "password",
+        }
+    }
+}
+
+impl ServerConfig {
+    /**
+     * 按配置的顺序返回该服务器可用的认证方式
+     */
+    pub fn auth_methods(&self) -> Vec<AuthMethod> {
+        let order = if self.auth_order.is_empty() {
+            vec![AuthMethod::Agent, AuthMethod::Key, AuthMethod::Password]
+        } else {
+            self.auth_order.clone()
+        };
+
+        let mut methods: Vec<AuthMethod> = Vec::new();
+        for method in order {
+            let available = match method {
+                // 显式写在 auth_order 中等同于开启 use_agent
+                AuthMethod::Agent => self.use_agent || self.auth_order.contains(&AuthMethod::Agent),
+                AuthMethod::Key => self.identity_file.is_some(),
+                AuthMethod::Password => !self.password.is_empty(),
+            };
 
-                Ok(config)
+            if available && !methods.contains(&method) {
+                methods.push(method);
             }
-            Err(_) => {
-                eprintln!("\n[Aspen Error] => {} \n", "读取配置json文件失败!".red());
-                process::exit(0);
+        }
+
+        methods
+    }
+
+    /**
+     * 将 password、passphrase 中的保险库引用替换为实际的值
+     */
+    pub fn with_resolved_secrets(&self) -> Result<ServerConfig> {
+        self.resolve_secrets_with(&mut None)
+    }
+
+    /**
+     * 同上,多台服务器共用一次解锁的保险库
+     */
+    pub fn resolve_secrets_with(&self, vault: &mut Option<Vault>) -> Result<ServerConfig> {
+        let mut config = self.clone();
+
+        config.password = resolve_secret(vault, &self.password)?;
+        config.passphrase = match &self.passphrase {
+            Some(value) => Some(resolve_secret(vault, value)?),
+            None => None,
+        };
+
+        Ok(config)
+    }
+
+    /**
+     * 键值标签以 key=value 逗号连接,用于表格、CSV 等文本输出
+     */
+    pub fn labels_text(&self) -> String {
+        self.labels.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<String>>().join(",")
+    }
+
+    /**
+     * 所属环境,取键值标签 env 的值
+     */
+    pub fn environment(&self) -> Option<&str> {
+        self.labels.get("env").map(String::as_str).filter(|env| !env.is_empty())
+    }
+
+    /**
+     * 私钥文件的实际地址(展开 ~)
+     */
+    pub fn identity_path(&self) -> Option<PathBuf> {
+        self.identity_file.as_ref().map(|file| expand_home(file))
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/**
+ * 展开以 ~ 开头的路径,无法获取主目录时原样返回
+ */
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), get_home_dir()) {
+        (Some(rest), Ok(home)) => home.join(rest),
+        (None, Ok(home)) if path == "~" => home,
+        _ => PathBuf::from(path),
+    }
+}
+
+/**
+ * 获取全部配置文件中的服务器数据,按标题合并
+ */
+pub fn get_config() -> Result<Vec<ServerConfig>> {
+    load_sources(false)
+}
+
+/**
+ * 依次读取全部配置文件,标题相同的服务器由靠后文件中的配置替换(保持首次出现的位置)
+ * allow_missing 为 true 时不存在的文件视为空
+ */
+pub fn load_sources(allow_missing: bool) -> Result<Vec<ServerConfig>> {
+    merge_sources(None, allow_missing)
+}
+
+// replace 用于写入前的校验: 以即将写入的内容代替该文件当前的内容
+fn merge_sources(replace: Option<(&Path, &[ServerConfig])>, allow_missing: bool) -> Result<Vec<ServerConfig>> {
+    let mut files = get_source_files()?;
+    if let Some((path, _)) = replace {
+        if !files.iter().any(|file| file == path) {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    let mut configs: Vec<ServerConfig> = Vec::new();
+    for file in files {
+        let file_configs = match replace {
+            Some((path, replaced)) if file == path => replaced.to_vec(),
+            _ if file.exists() => read_server_config(&file)?,
+            _ if allow_missing => continue,
+            _ => return Err(AspenError::Config(format!("💔 您的配置文件({})不存在 ", file.display()))),
+        };
+
+        for mut config in file_configs {
+            config.source = file.clone();
+            match configs.iter().position(|other| other.title == config.title) {
+                Some(index) => configs[index] = config,
+                None => configs.push(config),
             }
         }
-    } else {
-        eprintln!("\n[Aspen Error] => {} \n", "无法打开配置文件!".red());
-        process::exit(0);
+    }
+
+    Ok(configs)
+}
+
+/**
+ * 按顺序展开配置文件列表中的地址,通配符按文件名排序展开,未匹配到文件的通配符忽略
+ */
+pub fn get_source_files() -> Result<Vec<PathBuf>> {
+    let paths = get_aspen_config()?.service_config_paths;
+    if paths.is_empty() {
+        return Err(AspenError::Config("💔 您的配置文件地址尚未设置,请先执行命令 path add 设置! ".to_string()));
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    for path in &paths {
+        for file in expand_source(path) {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/**
+ * 展开单个配置文件地址(~ 与通配符)
+ */
+pub fn expand_source(path: &str) -> Vec<PathBuf> {
+    let path = expand_home(path);
+    if !is_glob(&path.to_string_lossy()) {
+        return vec![path];
+    }
+
+    let mut files: Vec<PathBuf> = match glob::glob(&path.to_string_lossy()) {
+        Ok(paths) => paths.flatten().filter(|file| file.is_file()).collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+
+    files
+}
+
+/**
+ * 地址中是否包含通配符
+ */
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/**
+ * 新增服务器写入的配置文件: 列表中最后一个不含通配符的地址
+ */
+pub fn get_servers_path() -> Result<PathBuf> {
+    let paths = get_aspen_config()?.service_config_paths;
+
+    match paths.iter().rev().find(|path| !is_glob(path)) {
+        Some(path) => Ok(expand_home(path)),
+        None => Err(AspenError::Config(
+            "💔 没有可写入的配置文件,请先执行命令 path add 添加一个不含通配符的地址! ".to_string(),
+        )),
+    }
+}
+
+/**
+ * 按序号(从 1 开始)、标题或别名精确查找服务器在列表中的下标
+ */
+pub fn find_server_index(configs: &[ServerConfig], query: &str) -> Option<usize> {
+    if let Ok(index) = query.trim().parse::<usize>() {
+        if index >= 1 && index <= configs.len() {
+            return Some(index - 1);
+        }
+    }
+
+    configs
+        .iter()
+        .position(|config| config.title == query)
+        .or_else(|| configs.iter().position(|config| config.aliases.iter().any(|alias| alias == query)))
+}
+
+// 校验列表中的单台服务器,别名、跳板机需与列表中的其他服务器对照
+fn validate_server(configs: &[ServerConfig], index: usize) -> Vec<String> {
+    let config = &configs[index];
+    let name = format!("第 {} 项({})", index + 1, config.title);
+    let mut errors: Vec<String> = Vec::new();
+
+    if config.title.trim().is_empty() {
+        errors.push(format!("{}: title 不能为空", name));
+    } else if config.title.trim().parse::<usize>().is_ok() {
+        errors.push(format!("{}: title 不能是纯数字,会与序号混淆", name));
+    }
+    if config.host.trim().is_empty() {
+        errors.push(format!("{}: host 不能为空", name));
+    }
+    if config.port == 0 || config.port > 65535 {
+        errors.push(format!("{}: port 必须在 1-65535 之间", name));
+    }
+    if config.username.trim().is_empty() {
+        errors.push(format!("{}: username 不能为空", name));
+    }
+    if configs[..index].iter().any(|other| other.title == config.title) {
+        errors.push(format!("{}: title 与其他服务器重复", name));
+    }
+    for alias in &config.aliases {
+        let duplicated = configs.iter().enumerate().any(|(other_index, other)| {
+            other.title == *alias || (other_index != index && other.aliases.contains(alias))
+        });
+        if alias.trim().is_empty() || alias.trim().parse::<usize>().is_ok() {
+            errors.push(format!("{}: 别名 {} 不能为空或纯数字", name, alias));
+        } else if duplicated {
+            errors.push(format!("{}: 别名 {} 与其他服务器的标题或别名重复", name, alias));
+        }
+    }
+    for key in config.labels.keys() {
+        if key.trim().is_empty() || key.contains([',', '=', '!']) {
+            errors.push(format!("{}: 标签键 {} 不能为空或包含 , = !", name, key));
+        }
+    }
+    if let Some(jump) = &config.proxy_jump {
+        match find_server_index(configs, jump) {
+            Some(jump_index) if jump_index == index => errors.push(format!("{}: proxy_jump 不能指向自身", name)),
+            Some(_) => {}
+            None => errors.push(format!("{}: proxy_jump 指定的跳板机 {} 不存在", name, jump)),
+        }
+    }
+    for forward in &config.forwards {
+        if forward.local_bind.trim().is_empty() || forward.remote_host.trim().is_empty() || forward.remote_port == 0 {
+            errors.push(format!("{}: 端口转发 {} 需填写 local_bind、remote_host、remote_port", name, forward.name));
+        }
+    }
+    for forward in &config.remote_forwards {
+        if forward.remote_listen().is_none() || forward.local_address.trim().is_empty() {
+            errors.push(format!("{}: 远程端口转发 {} 的 remote_bind 或 local_address 不合法", name, forward.name));
+        }
+    }
+
+    errors
+}
+
+// 校验即将写入某个文件的服务器: 文件内标题不能重复,别名、跳板机与其他配置文件合并后对照
+fn validate_file(file_path: &Path, configs: &[ServerConfig]) -> Result<Vec<String>> {
+    let mut errors: Vec<String> = Vec::new();
+    for (index, config) in configs.iter().enumerate() {
+        if configs[..index].iter().any(|other| other.title == config.title) {
+            errors.push(format!("{}: title 在 {} 中重复", config.title, file_path.display()));
+        }
+    }
+
+    let merged = merge_sources(Some((file_path, configs)), true)?;
+    for index in 0..merged.len() {
+        if merged[index].source == file_path {
+            errors.extend(validate_server(&merged, index));
+        }
+    }
+
+    Ok(errors)
+}
+
+/**
+ * 校验并写回服务器配置文件(先写临时文件再替换,保证原子性)
+ * TOML 在原文件上逐项修改,保留注释与格式; JSON/YAML 整体重新生成,原文件有注释时先给出警告
+ */
+pub fn write_server_config(file_path: &Path, configs: &[ServerConfig]) -> Result<()> {
+    let errors = validate_file(file_path, configs)?;
+    if !errors.is_empty() {
+        return Err(AspenError::Config(format!("服务器配置校验失败!\n  {}", errors.join("\n  "))));
+    }
+
+    let format = ConfigFormat::from_path(file_path);
+    let original = fs::read_to_string(file_path).unwrap_or_default();
+    let content = format
+        .serialize_over(&original, configs)
+        .map_err(|e| AspenError::Config(format!("服务器配置转为{}失败! ({})", format.name(), e)))?;
+
+    if format.has_comments(&original) {
+        confirm_drop_comments(file_path)?;
+    }
+
+    // 临时文件沿用原文件的权限,避免保存密码的配置文件在替换后变为默认权限
+    let permissions = fs::metadata(file_path).ok().map(|metadata| metadata.permissions());
+    let tmp_path = file_path.with_extension("aspen.tmp");
+    let result = File::create(&tmp_path)
+        .and_then(|mut file| file.write_all(content.as_bytes()).and_then(|_| file.sync_all()))
+        .and_then(|_| match permissions {
+            Some(permissions) => fs::set_permissions(&tmp_path, permissions),
+            None => Ok(()),
+        })
+        .and_then(|_| fs::rename(&tmp_path, file_path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(AspenError::Io(format!("写入配置文件 {} 失败!", file_path.display())));
+    }
+
+    Ok(())
+}
+
+// JSON、YAML 文件重新生成后注释会丢失,写入前需输入 yes 确认,非交互环境直接拒绝
+fn confirm_drop_comments(file_path: &Path) -> Result<()> {
+    let message = format!("配置文件 {} 中的注释无法保留,写回后将丢失", file_path.display());
+    if !stdin().is_terminal() {
+        return Err(AspenError::Usage(format!(
+            "{},非交互环境下不会写入;请先删除注释或改用 TOML 格式的配置文件",
+            message
+        )));
+    }
+
+    eprintln!("\n[Aspen Warning] => {}", message.yellow());
+    print!("输入 {} 继续写入: ", "yes".green());
+    stdout().flush().map_err(|_| AspenError::Io("输出提示失败!".to_string()))?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer).map_err(|_| AspenError::Io("读取输入失败!".to_string()))?;
+    if !answer.trim().eq_ignore_ascii_case("yes") {
+        return Err(AspenError::Cancelled);
+    }
+
+    Ok(())
+}
+
+/**
+ * 读取服务器配置文件,按扩展名选择 JSON、YAML 或 TOML 解析
+ */
+pub fn read_server_config(file_path: &PathBuf) -> Result<Vec<ServerConfig>> {
+    let mut file = File::open(file_path)
+        .map_err(|_| AspenError::Io(format!("无法打开配置文件 {}!", file_path.display())))?;
+
+    // 读取文件内容
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(|_| AspenError::Io(format!("读取配置文件 {} 失败!", file_path.display())))?;
+
+    let format = ConfigFormat::from_path(file_path);
+    let mut configs = format.deserialize(&contents).map_err(|e| {
+        AspenError::Config(format!(
+            "解析配置{}文件 {} 失败,请检查您的配置文件内容是否有误! ({})", format.name(), file_path.display(), e
+        ))
+    })?;
+    configs.iter_mut().for_each(|server| server.source = file_path.clone());
+
+    Ok(configs)
+}
+
+/**
+ * 服务器配置文件的格式,由扩展名决定,其他扩展名按 JSON 处理
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+// TOML 顶层必须是表,服务器列表写在 [[servers]] 中
+#[derive(Serialize, Deserialize)]
+struct TomlServers {
+    #[serde(default)]
+    servers: Vec<ServerConfig>,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &Path) -> ConfigFormat {
+        match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()).as_deref() {
+            Some("yaml") | Some("yml") => ConfigFormat::Yaml,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+
+    /**
+     * 解析配置内容,JSON 允许 // 与 /* */ 注释
+     */
+    pub fn deserialize(&self, contents: &str) -> std::result::Result<Vec<ServerConfig>, String> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(&strip_json_comments(contents)).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => {
+                // 空文件视为空列表
+                if contents.trim().is_empty() {
+                    return Ok(Vec::new());
+                }
+                serde_yaml::from_str(contents).map_err(|e| e.to_string())
+            }
+            ConfigFormat::Toml => toml::from_str::<TomlServers>(contents).map(|file| file.servers).map_err(|e| e.to_string()),
+        }
+    }
+
+    pub fn serialize(&self, configs: &[ServerConfig]) -> std::result::Result<String, String> {
+        match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(configs).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::to_string(configs).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => {
+                toml::to_string_pretty(&TomlServers { servers: configs.to_vec() }).map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /**
+     * 以原文件内容为基础生成新内容: TOML 只修改有变化的服务器与字段,其余内容(注释、格式)原样保留
+     * 原文件为空或无法解析时与 serialize 相同
+     */
+    pub fn serialize_over(&self, original: &str, configs: &[ServerConfig]) -> std::result::Result<String, String> {
+        match (self, original.parse::<DocumentMut>()) {
+            (ConfigFormat::Toml, Ok(document)) if !original.trim().is_empty() => merge_toml(document, configs),
+            _ => self.serialize(configs),
+        }
+    }
+
+    /**
+     * 原文件中是否有重新生成时会丢失的注释,TOML 按原格式修改不会丢失
+     */
+    pub fn has_comments(&self, contents: &str) -> bool {
+        match self {
+            ConfigFormat::Json => strip_json_comments(contents) != contents,
+            ConfigFormat::Yaml => contents.lines().any(yaml_line_has_comment),
+            ConfigFormat::Toml => false,
+        }
+    }
+}
+
+// 把服务器列表写入已有的 TOML 文档: 按标题对应原有的 [[servers]] 表,标题变化(重命名)的对应同一位置上剩余的表
+fn merge_toml(mut document: DocumentMut, configs: &[ServerConfig]) -> std::result::Result<String, String> {
+    let mut old_tables: Vec<Option<Table>> = match document.remove("servers").map(Item::into_array_of_tables) {
+        Some(Ok(tables)) => tables.into_iter().map(Some).collect(),
+        Some(Err(_)) => return Err("servers 不是 [[servers]] 表数组".to_string()),
+        None => Vec::new(),
+    };
+    let old_title = |table: &Table| table.get("title").and_then(Item::as_str).map(str::to_string);
+
+    let mut matched: Vec<Option<Table>> = configs
+        .iter()
+        .map(|config| {
+            let index = old_tables
+                .iter()
+                .position(|table| table.as_ref().and_then(old_title).as_deref() == Some(config.title.as_str()))?;
+            old_tables[index].take()
+        })
+        .collect();
+    for (slot, old) in matched.iter_mut().zip(old_tables.iter_mut()) {
+        if slot.is_none() {
+            *slot = old.take();
+        }
+    }
+
+    let mut servers = ArrayOfTables::new();
+    for (config, old) in configs.iter().zip(matched) {
+        let fresh = server_table(config)?;
+        servers.push(match old {
+            Some(mut table) => {
+                update_table(&mut table, fresh);
+                table
+            }
+            None => fresh,
+        });
+    }
+    document.insert("servers", Item::ArrayOfTables(servers));
+
+    Ok(document.to_string())
+}
+
+// 单台服务器的 TOML 表,转发列表写成 [[servers.forwards]] 表数组,其余保持行内格式
+fn server_table(config: &ServerConfig) -> std::result::Result<Table, String> {
+    let mut table = toml_edit::ser::to_document(config).map_err(|e| e.to_string())?.as_table().clone();
+    for key in ["forwards", "remote_forwards"] {
+        if let Some(item) = table.remove(key) {
+            let item = match item.into_array_of_tables() {
+                Ok(tables) => Item::ArrayOfTables(tables),
+                Err(item) => item,
+            };
+            table.insert(key, item);
+        }
+    }
+    table.fmt();
+
+    Ok(table)
+}
+
+// 只替换值有变化的字段并保留其行尾注释与写法(行内表或 [servers.xxx] 子表),删除不再存在的字段
+fn update_table(table: &mut Table, fresh: Table) {
+    let keys: Vec<String> = fresh.iter().map(|(key, _)| key.to_string()).collect();
+    table.retain(|key, _| keys.iter().any(|fresh_key| fresh_key == key));
+
+    for (key, mut item) in fresh {
+        match table.get_mut(&key) {
+            Some(old) if plain_value(old).is_some() && plain_value(old) == plain_value(&item) => {}
+            Some(old) => {
+                if let (Some(old_value), Some(value)) = (old.as_value(), item.as_value_mut()) {
+                    *value.decor_mut() = old_value.decor().clone();
+                }
+                if let (Item::Table(old_table), Item::Value(toml_edit::Value::InlineTable(inline))) = (&*old, &item) {
+                    let mut sub_table = inline.clone().into_table();
+                    *sub_table.decor_mut() = old_table.decor().clone();
+                    item = Item::Table(sub_table);
+                }
+                *old = item;
+            }
+            None => {
+                table.insert(&key, item);
+            }
+        }
+    }
+}
+
+// 去掉格式与注释后的值,用于判断字段是否有变化
+fn plain_value(item: &Item) -> Option<toml::Value> {
+    let value = item.clone().into_value().ok()?;
+    toml::from_str::<toml::Table>(&format!("value = {}", value)).ok()?.remove("value")
+}
+
+// YAML 中 # 开头或前面是空白、且不在引号内的部分为注释
+fn yaml_line_has_comment(line: &str) -> bool {
+    let mut quote: Option<char> = None;
+    let mut last = ' ';
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') if last.is_whitespace() || last == ':' || last == '-' => quote = Some(c),
+            (None, '#') if last.is_whitespace() => return true,
+            _ => {}
+        }
+        last = c;
+    }
+    false
+}
+
+// 去掉 JSON 中字符串之外的注释,保留换行以便报错行号不变
+fn strip_json_comments(contents: &str) -> String {
+    let mut output = String::with_capacity(contents.len());
+    let mut chars = contents.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|next| *next != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        output.push('\n');
+                    }
+                    if last == '*' && next == '/' {
+                        break;
+                    }
+                    last = next;
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML_SERVERS: &str = r#"# 服务器列表
+
+# 主站
+[[servers]]
+title = "Web1"   # 生产
+category = "prod"
+host = "10.0.0.1"
+port = 22
+username = "root"
+password = ""
+remark = "web"
+owner = "ops"
+
+[[servers.forwards]]
+name = "pg"
+local_bind = "15432"
+remote_host = "127.0.0.1"
+remote_port = 5432
+
+# 数据库
+[[servers]]
+title = "Db1"
+category = "prod"
+host = "10.0.0.2"
+port = 22
+username = "root"
+password = ""
+remark = "db"
+
+[servers.labels]
+env = "prod"
+"#;
+
+    fn read_toml() -> Vec<ServerConfig> {
+        ConfigFormat::Toml.deserialize(TOML_SERVERS).unwrap()
+    }
+
+    #[test]
+    fn toml_round_trip_keeps_file_unchanged() {
+        let output = ConfigFormat::Toml.serialize_over(TOML_SERVERS, &read_toml()).unwrap();
+        assert_eq!(output, TOML_SERVERS);
+    }
+
+    #[test]
+    fn toml_edit_keeps_comments_and_unknown_keys() {
+        let mut configs = read_toml();
+        configs[0].host = "10.0.0.9".to_string();
+        configs[1].labels.insert("env".to_string(), "staging".to_string());
+
+        let output = ConfigFormat::Toml.serialize_over(TOML_SERVERS, &configs).unwrap();
+        let expected = TOML_SERVERS
+            .replace("host = \"10.0.0.1\"", "host = \"10.0.0.9\"")
+            .replace("env = \"prod\"", "env = \"staging\"");
+        assert_eq!(output, expected);
+
+        let reread = ConfigFormat::Toml.deserialize(&output).unwrap();
+        assert_eq!(reread[0].extra.get("owner"), Some(&serde_json::json!("ops")));
+        assert_eq!(reread[0].forwards.len(), 1);
+    }
+
+    #[test]
+    fn toml_edit_renames_removes_and_appends_servers() {
+        let mut configs = read_toml();
+        configs[1].title = "Db2".to_string();
+        let renamed = ConfigFormat::Toml.serialize_over(TOML_SERVERS, &configs).unwrap();
+        assert_eq!(renamed, TOML_SERVERS.replace("title = \"Db1\"", "title = \"Db2\""));
+
+        configs.remove(0);
+        let mut added = configs[0].clone();
+        added.title = "Db3".to_string();
+        configs.push(added);
+
+        let output = ConfigFormat::Toml.serialize_over(&renamed, &configs).unwrap();
+        assert!(output.contains("# 数据库\n[[servers]]\ntitle = \"Db2\""));
+        assert!(!output.contains("Web1"));
+
+        let titles: Vec<String> = ConfigFormat::Toml.deserialize(&output).unwrap().into_iter().map(|s| s.title).collect();
+        assert_eq!(titles, vec!["Db2", "Db3"]);
+    }
+
+    #[test]
+    fn json_and_yaml_keep_unknown_keys() {
+        let json = r#"[{"title": "A", "category": "", "host": "h", "port": 22, "username": "u", "remark": "", "zone": {"id": 1}}]"#;
+        let configs = ConfigFormat::Json.deserialize(json).unwrap();
+        let output = ConfigFormat::Json.serialize_over(json, &configs).unwrap();
+        assert!(output.contains("\"zone\": {\n      \"id\": 1\n    }"));
+
+        let yaml = ConfigFormat::Yaml.serialize(&configs).unwrap();
+        let reread = ConfigFormat::Yaml.deserialize(&yaml).unwrap();
+        assert_eq!(reread[0].extra.get("zone"), Some(&serde_json::json!({"id": 1})));
+    }
+
+    #[test]
+    fn strip_json_comments_keeps_strings_and_lines() {
+        let json = "[\n  // 注释\n  {\"remark\": \"http://a // b\", /* 行内 */ \"title\": \"a\\\"/*b*/\"}\n  /* 多行\n  注释 */\n]";
+        let stripped = strip_json_comments(json);
+
+        assert_eq!(stripped, "[\n  \n  {\"remark\": \"http://a // b\",  \"title\": \"a\\\"/*b*/\"}\n  \n\n]");
+        assert_eq!(stripped.lines().count(), json.lines().count());
+        let value: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value[0]["title"], "a\"/*b*/");
+    }
+
+    #[test]
+    fn remote_forward_parse() {
+        let forward = RemoteForwardConfig::parse("8080:127.0.0.1:3000").unwrap();
+        assert_eq!((forward.remote_bind.as_str(), forward.local_address.as_str()), ("8080", "127.0.0.1:3000"));
+        assert_eq!(forward.remote_listen(), Some((None, 8080)));
+        assert_eq!(forward.local_target(), "127.0.0.1:3000");
+
+        let forward = RemoteForwardConfig::parse("0.0.0.0:8080:localhost:3000").unwrap();
+        assert_eq!(forward.remote_bind, "0.0.0.0:8080");
+        assert_eq!(forward.remote_listen(), Some((Some("0.0.0.0".to_string()), 8080)));
+
+        assert!(RemoteForwardConfig::parse("8080:3000").is_none());
+        assert!(RemoteForwardConfig::parse("a:b:c:d:e").is_none());
+        assert!(RemoteForwardConfig::parse("http:127.0.0.1:3000").is_none());
+        assert!(RemoteForwardConfig::parse("70000:127.0.0.1:3000").is_none());
+    }
+
+    fn auth_server(fields: serde_json::Value) -> ServerConfig {
+        let mut server = serde_json::json!({
+            "title": "Web1",
+            "category": "prod",
+            "host": "10.0.0.1",
+            "port": 22,
+            "username": "root",
+            "remark": "",
+        });
+        server.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(server).unwrap()
+    }
+
+    #[test]
+    fn auth_methods_default_order_skips_missing_credentials() {
+        use AuthMethod::{Agent, Key, Password};
+
+        assert_eq!(auth_server(serde_json::json!({})).auth_methods(), vec![]);
+        assert_eq!(auth_server(serde_json::json!({"password": "p"})).auth_methods(), vec![Password]);
+        assert_eq!(
+            auth_server(serde_json::json!({"password": "p", "identity_file": "~/.ssh/id_ed25519", "use_agent": true})).auth_methods(),
+            vec![Agent, Key, Password]
+        );
+        assert_eq!(auth_server(serde_json::json!({"password": "p", "identity_file": "~/.ssh/id"})).auth_methods(), vec![Key, Password]);
+    }
+
+    #[test]
+    fn auth_methods_follow_auth_order() {
+        use AuthMethod::{Agent, Key, Password};
+
+        let config = auth_server(serde_json::json!({
+            "password": "p",
+            "identity_file": "~/.ssh/id",
+            "auth_order": ["password", "key"],
+        }));
+        assert_eq!(config.auth_methods(), vec![Password, Key]);
+
+        // auth_order 中写了 agent 即视为开启,未配置凭据的方式跳过,重复的只保留第一次
+        let config = auth_server(serde_json::json!({
+            "password": "",
+            "auth_order": ["key", "agent", "password", "agent"],
+        }));
+        assert_eq!(config.auth_methods(), vec![Agent]);
+
+        // 未写在 auth_order 中的方式不会使用
+        let config = auth_server(serde_json::json!({"password": "p", "use_agent": true, "auth_order": ["password"]}));
+        assert_eq!(config.auth_methods(), vec![Password]);
+    }
+
+    #[test]
+    fn detects_comments_lost_on_rewrite() {
+        assert!(ConfigFormat::Json.has_comments("[ // 注释\n]"));
+        assert!(!ConfigFormat::Json.has_comments(r#"[{"remark": "http://a"}]"#));
+        assert!(ConfigFormat::Yaml.has_comments("# 注释\n- title: A"));
+        assert!(ConfigFormat::Yaml.has_comments("- title: A  # 注释"));
+        assert!(!ConfigFormat::Yaml.has_comments("- remark: 'a # b'\n  host: a#b"));
+        assert!(!ConfigFormat::Toml.has_comments(TOML_SERVERS));
     }
 }
@@ -0,0 +1,371 @@
+use std::{
+    collections::VecDeque,
+    io::{self, stderr, stdout, Read, Write},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use clap::ArgMatches;
+use colored::Colorize;
+use prettytable::{row, Table};
+use ssh2::Session;
+
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::command::table_format;
+use crate::ssh_module::config::{get_config, ServerConfig};
+use crate::ssh_module::guard::confirm_dangerous;
+use crate::ssh_module::selector::{select_servers, Selector};
+use crate::ssh_module::session::{connect_session, resolve_jump_chain, retry_blocking, try_connect_session};
+use crate::ssh_module::target::resolve_target;
+
+// 批量执行时单台服务器的结果
+struct HostResult {
+    title: String,
+    host: String,
+    status: Result<i32>,
+    duration: Duration,
+}
+
+// 批量执行的一台服务器及其跳板机链,均已解析保险库引用
+struct HostJob {
+    config: ServerConfig,
+    jumps: Vec<ServerConfig>,
+}
+
+impl HostResult {
+    fn succeeded(&self) -> bool {
+        matches!(self.status, Ok(0))
+    }
+}
+
+/**
+ * exec 命令实现: 在服务器上执行单条命令,以远程命令的退出码退出
+ * 与 ssh 一致,链接、认证等工具自身的错误以 255 退出
+ */
+pub fn impl_exec_action(matches: &ArgMatches) -> Result<()> {
+    match exec_command(matches) {
+        Ok(0) => Ok(()),
+        Ok(status) => Err(AspenError::Exit(status)),
+        Err(e) => Err(AspenError::Exec(Box::new(e))),
+    }
+}
+
+/**
+ * 在服务器上执行 exec 命令指定的命令,返回远程命令的退出码
+ */
+pub fn exec_command(matches: &ArgMatches) -> Result<i32> {
+    let configs = get_config()?;
+    let config = resolve_target(&configs, matches.get_one::<String>("target").unwrap(), &Selector::from_filter_args(matches)?)?;
+    let command = join_command(matches);
+    confirm_dangerous(&config, "执行命令", matches.get_flag("yes-i-am-sure"))?;
+
+    let sess = connect_session(&config)?;
+
+    let mut out = stdout();
+    let mut err = stderr();
+    let result = run_command(
+        &sess,
+        &command,
+        |data| {
+            let _ = out.write_all(data).and_then(|_| out.flush());
+        },
+        |data| {
+            let _ = err.write_all(data).and_then(|_| err.flush());
+        },
+    );
+
+    result.map_err(|e| AspenError::Remote(format!("执行命令失败: {}", e)))
+}
+
+/**
+ * exec-all 命令实现: 在选中的多台服务器上并发执行同一条命令
+ * 任意一台失败(链接失败或退出码非 0)时以 1 退出
+ */
+pub fn impl_exec_all_action(matches: &ArgMatches) -> Result<()> {
+    let configs = get_config()?;
+    let selector = matches.get_one::<String>("selector").unwrap();
+    let command = join_command(matches);
+    let parallel = *matches.get_one::<u64>("parallel").unwrap() as usize;
+    let grouped = matches.get_flag("group");
+
+    let filter = Selector::from_filter_args(matches)?;
+    let selected: Vec<ServerConfig> = select_servers(&configs, selector)?
+        .into_iter()
+        .filter(|config| filter.matches(config))
+        .collect();
+    if selected.is_empty() {
+        return Err(AspenError::NotFound(format!("没有与 {} 匹配的服务器!", selector)));
+    }
+    for config in &selected {
+        confirm_dangerous(config, "执行命令", matches.get_flag("yes-i-am-sure"))?;
+    }
+
+    // 在主线程中统一解析服务器及其跳板机的保险库引用,只需解锁一次
+    let mut vault = None;
+    let jobs: Vec<HostJob> = selected
+        .iter()
+        .map(|config| {
+            Ok(HostJob {
+                config: config.resolve_secrets_with(&mut vault)?,
+                jumps: resolve_jump_chain(config, &mut vault)?,
+            })
+        })
+        .collect::<Result<Vec<HostJob>>>()?;
+
+    println!(
+        "\n[Aspen Waiting] ==> 正在 {} 台服务器上执行: {}\n",
+        jobs.len().to_string().green(), command.green()
+    );
+
+    let results = run_on_hosts(jobs, &command, parallel, grouped);
+    print_exec_table(&results);
+
+    let failed = results.iter().filter(|result| !result.succeeded()).count();
+    if failed > 0 {
+        eprintln!("\n[Aspen Error] => {}\n", format!("{} 台服务器执行失败!", failed).red());
+        return Err(AspenError::Exit(1));
+    }
+
+    println!("\n[Aspen Success] ==> {}\n", "全部执行成功".green());
+    Ok(())
+}
+
+// 最多 parallel 个线程同时执行,结果按服务器原有顺序返回
+fn run_on_hosts(jobs: Vec<HostJob>, command: &str, parallel: usize, grouped: bool) -> Vec<HostResult> {
+    let total = jobs.len();
+    let workers = parallel.min(total);
+    let queue = Arc::new(Mutex::new(jobs.into_iter().enumerate().collect::<VecDeque<_>>()));
+    let (sender, receiver) = mpsc::channel::<(usize, HostResult)>();
+
+    for _ in 0..workers {
+        let queue = Arc::clone(&queue);
+        let sender = sender.clone();
+        let command = command.to_string();
+
+        thread::spawn(move || loop {
+            let next = queue.lock().unwrap().pop_front();
+            let (index, job) = match next {
+                Some(job) => job,
+                None => break,
+            };
+
+            let result = if grouped {
+                run_grouped(&job, &command)
+            } else {
+                run_streaming(&job, &command)
+            };
+            if sender.send((index, result)).is_err() {
+                break;
+            }
+        });
+    }
+    drop(sender);
+
+    let mut results: Vec<(usize, HostResult)> = receiver.iter().collect();
+    results.sort_by_key(|(index, _)| *index);
+
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+// 逐行输出,每行带上服务器标题前缀
+fn run_streaming(job: &HostJob, command: &str) -> HostResult {
+    let out_prefix = format!("[{}] ", job.config.title).green().to_string();
+    let err_prefix = format!("[{}] ", job.config.title).red().to_string();
+    let mut out = PrefixedLines::new(out_prefix, false);
+    let mut err = PrefixedLines::new(err_prefix.clone(), true);
+
+    let result = execute(job, command, |data| out.push(data), |data| err.push(data));
+    out.finish();
+    err.finish();
+
+    if let Err(e) = &result.status {
+        eprintln!("{}{}", err_prefix, e.to_string().red());
+    }
+
+    result
+}
+
+// 收集全部输出,执行结束后整块打印
+fn run_grouped(job: &HostJob, command: &str) -> HostResult {
+    let config = &job.config;
+    let mut out: Vec<u8> = Vec::new();
+    let mut err: Vec<u8> = Vec::new();
+
+    let result = execute(job, command, |data| out.extend_from_slice(data), |data| err.extend_from_slice(data));
+
+    let mut stdout = stdout().lock();
+    let mut stderr = stderr().lock();
+    let _ = writeln!(stdout, "{}", format!("===== {} ({}) =====", config.title, config.host).green());
+    let _ = stdout.write_all(&out).and_then(|_| stdout.flush());
+    let _ = stderr.write_all(&err).and_then(|_| stderr.flush());
+    if let Err(e) = &result.status {
+        let _ = writeln!(stderr, "{}", e.to_string().red());
+    }
+    let _ = writeln!(stdout);
+
+    result
+}
+
+fn execute(
+    job: &HostJob,
+    command: &str,
+    on_stdout: impl FnMut(&[u8]),
+    on_stderr: impl FnMut(&[u8]),
+) -> HostResult {
+    let start = Instant::now();
+    let status = try_connect_session(&job.config, &job.jumps)
+        .and_then(|sess| run_command(&sess, command, on_stdout, on_stderr));
+
+    HostResult {
+        title: job.config.title.clone(),
+        host: job.config.host.clone(),
+        status,
+        duration: start.elapsed(),
+    }
+}
+
+fn print_exec_table(results: &[HostResult]) {
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(row!["ID", "Title", "Host", "Exit Code", "Duration", "Result"]);
+
+    for (index, result) in results.iter().enumerate() {
+        let (code, message) = match &result.status {
+            Ok(code) => (code.to_string(), if *code == 0 { "ok".to_string() } else { "failed".to_string() }),
+            Err(e) => ("-".to_string(), e.to_string()),
+        };
+        let duration = format!("{:.2}s", result.duration.as_secs_f64());
+
+        table.add_row(row![(index + 1), result.title, result.host, code, duration, message]);
+    }
+
+    table.printstd();
+}
+
+// 按行缓冲远程输出,加上前缀后整行写出,避免多台服务器的输出交错在同一行
+struct PrefixedLines {
+    prefix: String,
+    to_stderr: bool,
+    buffer: Vec<u8>,
+}
+
+impl PrefixedLines {
+    fn new(prefix: String, to_stderr: bool) -> PrefixedLines {
+        PrefixedLines { prefix, to_stderr, buffer: Vec::new() }
+    }
+
+    fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+
+        if let Some(end) = self.buffer.iter().rposition(|b| *b == b'\n') {
+            let lines: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.write(&lines);
+        }
+    }
+
+    // 输出末尾不以换行结束的剩余内容
+    fn finish(&mut self) {
+        if !self.buffer.is_empty() {
+            let mut rest = std::mem::take(&mut self.buffer);
+            rest.push(b'\n');
+            self.write(&rest);
+        }
+    }
+
+    fn write(&self, lines: &[u8]) {
+        let mut text: Vec<u8> = Vec::with_capacity(lines.len() + self.prefix.len());
+        for line in lines.split_inclusive(|b| *b == b'\n') {
+            text.extend_from_slice(self.prefix.as_bytes());
+            text.extend_from_slice(line);
+        }
+
+        let _ = if self.to_stderr {
+            stderr().lock().write_all(&text)
+        } else {
+            stdout().lock().write_all(&text)
+        };
+    }
+}
+
+/**
+ * 拼接 -- 之后的命令参数,交由远程 shell 解释
+ */
+pub fn join_command(matches: &ArgMatches) -> String {
+    matches
+        .get_many::<String>("command")
+        .unwrap()
+        .cloned()
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/**
+ * 通过 exec 通道执行命令,stdout、stderr 分别回调,返回远程退出码
+ * 远程进程被信号终止时与 ssh 一致返回 255
+ */
+pub fn run_command(
+    sess: &Session,
+    command: &str,
+    mut on_stdout: impl FnMut(&[u8]),
+    mut on_stderr: impl FnMut(&[u8]),
+) -> Result<i32> {
+    let mut channel = sess.channel_session().map_err(remote_error)?;
+    channel.exec(command).map_err(remote_error)?;
+
+    // 非阻塞轮流读取两个流,避免其中一个写满缓冲区时互相阻塞
+    sess.set_blocking(false);
+
+    let mut buf = [0u8; 16 * 1024];
+    let result = loop {
+        let mut idle = true;
+
+        match read_stream(&mut channel.stream(0), &mut buf) {
+            Ok(0) => {}
+            Ok(n) => {
+                on_stdout(&buf[..n]);
+                idle = false;
+            }
+            Err(e) => break Err(AspenError::Remote(e.to_string())),
+        }
+
+        match read_stream(&mut channel.stderr(), &mut buf) {
+            Ok(0) => {}
+            Ok(n) => {
+                on_stderr(&buf[..n]);
+                idle = false;
+            }
+            Err(e) => break Err(AspenError::Remote(e.to_string())),
+        }
+
+        if idle {
+            if channel.eof() {
+                break Ok(());
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    };
+
+    let result = result.and_then(|_| {
+        retry_blocking(|| channel.wait_close()).map_err(remote_error)?;
+        if matches!(channel.exit_signal(), Ok(signal) if signal.exit_signal.is_some()) {
+            return Ok(255);
+        }
+        channel.exit_status().map_err(remote_error)
+    });
+
+    sess.set_blocking(true);
+
+    result
+}
+
+fn remote_error(e: ssh2::Error) -> AspenError {
+    AspenError::Remote(e.message().to_string())
+}
+
+// 读取一次,暂无数据时返回 0
+fn read_stream(stream: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    match stream.read(buf) {
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(0),
+        result => result,
+    }
+}
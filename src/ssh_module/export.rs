@@ -0,0 +1,349 @@
+use std::fs;
+use clap::ArgMatches;
+use colored::Colorize;
+use serde_yaml::{Mapping, Value};
+
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::config::{find_server_index, get_config, ServerConfig};
+use crate::vault_module::store::VAULT_PREFIX;
+
+/**
+ * export 命令实现: 将服务器列表导出为其他工具可用的格式,输出到标准输出或文件
+ */
+pub fn impl_export_action(matches: &ArgMatches) -> Result<()> {
+    let mut configs = get_config()?;
+    let format = matches.get_one::<String>("format").unwrap().as_str();
+
+    // 不导出密码时同时去掉私钥密码
+    if matches.get_flag("no-passwords") {
+        for config in configs.iter_mut() {
+            config.password = String::new();
+            config.passphrase = None;
+        }
+    } else if matches.get_flag("show-secrets") {
+        let mut vault = None;
+        configs = configs.iter().map(|config| config.resolve_secrets_with(&mut vault)).collect::<Result<Vec<ServerConfig>>>()?;
+    } else if format != "json" {
+        // 其他工具无法解析保险库引用,JSON 保留引用,仍可作为本工具的配置文件
+        let omitted = omit_vault_references(&mut configs);
+        if omitted > 0 {
+            eprintln!(
+                "\n[Aspen Warning] => {}\n",
+                format!("{} 台服务器的密码为保险库引用,未导出(使用 --show-secrets 导出实际的值)", omitted).yellow()
+            );
+        }
+    }
+
+    let content = match format {
+        "ssh-config" => to_ssh_config(&configs),
+        "ansible-ini" => to_ansible_ini(&configs),
+        "ansible-yaml" => to_ansible_yaml(&configs),
+        "csv" => to_csv(&configs),
+        _ => serde_json::to_string_pretty(&configs).unwrap() + "\n",
+    };
+
+    match matches.get_one::<String>("output") {
+        Some(output) => {
+            fs::write(output, content).map_err(|e| AspenError::Io(format!("写入 {} 失败: {}", output, e)))?;
+            println!("\n[Aspen Success] ==> 已导出 {} 台服务器到 {}\n", configs.len(), output.green());
+        }
+        None => print!("{}", content),
+    }
+
+    Ok(())
+}
+
+// OpenSSH 配置,密码无法写入,需配合 ssh-agent 或私钥使用
+fn to_ssh_config(configs: &[ServerConfig]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    for config in configs {
+        let mut names = vec![host_name(&config.title)];
+        names.extend(config.aliases.iter().map(|alias| host_name(alias)));
+
+        if !config.remark.is_empty() {
+            lines.push(format!("# {}", config.remark));
+        }
+        lines.push(format!("Host {}", names.join(" ")));
+        lines.push(format!("    HostName {}", config.host));
+        lines.push(format!("    Port {}", config.port));
+        lines.push(format!("    User {}", config.username));
+        if let Some(identity_file) = &config.identity_file {
+            lines.push(format!("    IdentityFile {}", identity_file));
+        }
+        if let Some(jump) = jump_server(config, configs) {
+            lines.push(format!("    ProxyJump {}", host_name(&jump.title)));
+        }
+        for forward in &config.forwards {
+            lines.push(format!("    LocalForward {} {}:{}", forward.bind_address(), forward.remote_host, forward.remote_port));
+        }
+        for forward in &config.remote_forwards {
+            lines.push(format!("    RemoteForward {} {}", forward.remote_bind, forward.local_target()));
+        }
+        lines.push(String::new());
+    }
+
+    lines.join("\n")
+}
+
+// Ansible INI 清单,分类作为主机组,未分类的主机写在最前面
+fn to_ansible_ini(configs: &[ServerConfig]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    for (group, members) in ansible_groups(configs) {
+        if let Some(group) = group {
+            lines.push(format!("[{}]", group));
+        }
+        for config in members {
+            let vars: Vec<String> = ansible_vars(config, configs)
+                .into_iter()
+                .map(|(key, value)| format!("{}={}", key, ini_value(&value)))
+                .collect();
+            lines.push(format!("{} {}", host_name(&config.title), vars.join(" ")));
+        }
+        lines.push(String::new());
+    }
+
+    lines.join("\n")
+}
+
+// Ansible YAML 清单,结构为 all.hosts(未分类)与 all.children.<分类>.hosts
+fn to_ansible_yaml(configs: &[ServerConfig]) -> String {
+    let mut all = Mapping::new();
+    let mut children = Mapping::new();
+
+    for (group, members) in ansible_groups(configs) {
+        let mut hosts = Mapping::new();
+        for config in members {
+            let mut vars = Mapping::new();
+            for (key, value) in ansible_vars(config, configs) {
+                let value = match value.parse::<u64>() {
+                    Ok(number) if key == "ansible_port" => Value::from(number),
+                    _ => Value::from(value),
+                };
+                vars.insert(Value::from(key), value);
+            }
+            hosts.insert(Value::from(host_name(&config.title)), Value::Mapping(vars));
+        }
+
+        match group {
+            Some(group) => {
+                let mut child = Mapping::new();
+                child.insert(Value::from("hosts"), Value::Mapping(hosts));
+                children.insert(Value::from(group), Value::Mapping(child));
+            }
+            None => {
+                all.insert(Value::from("hosts"), Value::Mapping(hosts));
+            }
+        }
+    }
+    if !children.is_empty() {
+        all.insert(Value::from("children"), Value::Mapping(children));
+    }
+
+    let mut root = Mapping::new();
+    root.insert(Value::from("all"), Value::Mapping(all));
+
+    serde_yaml::to_string(&root).unwrap()
+}
+
+fn to_csv(configs: &[ServerConfig]) -> String {
+    let mut lines: Vec<String> = vec![
+        "title,category,host,port,username,password,identity_file,use_agent,aliases,tags,labels,proxy_jump,remark".to_string(),
+    ];
+
+    for config in configs {
+        let fields = [
+            config.title.clone(),
+            config.category.clone(),
+            config.host.clone(),
+            config.port.to_string(),
+            config.username.clone(),
+            config.password.clone(),
+            config.identity_file.clone().unwrap_or_default(),
+            config.use_agent.to_string(),
+            config.aliases.join(","),
+            config.tags.join(","),
+            config.labels_text(),
+            config.proxy_jump.clone().unwrap_or_default(),
+            config.remark.clone(),
+        ];
+        lines.push(fields.iter().map(|field| csv_field(field)).collect::<Vec<String>>().join(","));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+// 去掉引用保险库的密码与私钥密码,返回涉及的服务器数量
+fn omit_vault_references(configs: &mut [ServerConfig]) -> usize {
+    let mut omitted = 0;
+    for config in configs.iter_mut() {
+        let mut changed = false;
+        if config.password.starts_with(VAULT_PREFIX) {
+            config.password = String::new();
+            changed = true;
+        }
+        if config.passphrase.as_deref().is_some_and(|passphrase| passphrase.starts_with(VAULT_PREFIX)) {
+            config.passphrase = None;
+            changed = true;
+        }
+        omitted += changed as usize;
+    }
+
+    omitted
+}
+
+/**
+ * CSV 字段转义: 含逗号、引号或换行时加双引号,引号重复一次
+ */
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// 按分类分组,保持服务器在配置中的顺序,未分类的组为 None 且排在最前
+fn ansible_groups(configs: &[ServerConfig]) -> Vec<(Option<String>, Vec<&ServerConfig>)> {
+    let mut groups: Vec<(Option<String>, Vec<&ServerConfig>)> = Vec::new();
+
+    for config in configs {
+        let group = Some(group_name(&config.category)).filter(|group| !group.is_empty());
+        match groups.iter_mut().find(|(name, _)| *name == group) {
+            Some((_, members)) => members.push(config),
+            None => groups.push((group, vec![config])),
+        }
+    }
+    groups.sort_by_key(|(group, _)| group.is_some());
+
+    groups
+}
+
+// 单台主机的 Ansible 变量,跳板机转为 ssh 的 ProxyJump 参数
+fn ansible_vars(config: &ServerConfig, configs: &[ServerConfig]) -> Vec<(&'static str, String)> {
+    let mut vars = vec![
+        ("ansible_host", config.host.clone()),
+        ("ansible_port", config.port.to_string()),
+        ("ansible_user", config.username.clone()),
+    ];
+    if !config.password.is_empty() {
+        vars.push(("ansible_password", config.password.clone()));
+    }
+    if let Some(identity_file) = &config.identity_file {
+        vars.push(("ansible_ssh_private_key_file", identity_file.clone()));
+    }
+
+    let hops: Vec<String> = jump_chain(config, configs)
+        .iter()
+        .map(|jump| format!("{}@{}:{}", jump.username, jump.host, jump.port))
+        .collect();
+    if !hops.is_empty() {
+        vars.push(("ansible_ssh_common_args", format!("-o ProxyJump={}", hops.join(","))));
+    }
+
+    vars
+}
+
+fn jump_server<'a>(config: &ServerConfig, configs: &'a [ServerConfig]) -> Option<&'a ServerConfig> {
+    let jump = config.proxy_jump.as_ref()?;
+    find_server_index(configs, jump).map(|index| &configs[index])
+}
+
+// 由远及近的全部跳板机,遇到循环引用时停止
+fn jump_chain<'a>(config: &ServerConfig, configs: &'a [ServerConfig]) -> Vec<&'a ServerConfig> {
+    let mut chain: Vec<&ServerConfig> = Vec::new();
+    let mut current = jump_server(config, configs);
+
+    while let Some(jump) = current {
+        if jump.title == config.title || chain.iter().any(|other| other.title == jump.title) {
+            break;
+        }
+        chain.insert(0, jump);
+        current = jump_server(jump, configs);
+    }
+
+    chain
+}
+
+// 主机名中不能有空白字符
+fn host_name(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<&str>>().join("-")
+}
+
+// Ansible 组名只能包含字母、数字与下划线,且不能以数字开头
+fn group_name(category: &str) -> String {
+    let name = category
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("_");
+
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("group_{}", name)
+    } else {
+        name
+    }
+}
+
+fn ini_value(value: &str) -> String {
+    if value.contains(char::is_whitespace) || value.contains(['"', '\'', '#', ';', '=']) {
+        format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(title: &str, password: &str) -> ServerConfig {
+        serde_json::from_value(serde_json::json!({
+            "title": title,
+            "category": "web",
+            "host": "10.0.0.1",
+            "port": 22,
+            "username": "root",
+            "password": password,
+            "remark": "",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn csv_field_escapes_special_characters() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn csv_rows_escape_every_field() {
+        let mut config = server("Web, 1", "p\"w");
+        config.remark = "first line\nsecond".to_string();
+        config.labels.insert("env".to_string(), "prod".to_string());
+        config.labels.insert("team".to_string(), "a".to_string());
+
+        let csv = to_csv(&[config]);
+        let row = csv.split_once('\n').unwrap().1;
+        assert_eq!(row, "\"Web, 1\",web,10.0.0.1,22,root,\"p\"\"w\",,false,,,\"env=prod,team=a\",,\"first line\nsecond\"\n");
+    }
+
+    #[test]
+    fn vault_references_are_not_exported() {
+        let mut configs = vec![server("Web1", "vault:web"), server("Web2", "plain")];
+        configs[1].passphrase = Some("vault:key".to_string());
+
+        assert_eq!(omit_vault_references(&mut configs), 2);
+        assert_eq!(configs[0].password, "");
+        assert_eq!((configs[1].password.as_str(), configs[1].passphrase.as_deref()), ("plain", None));
+
+        let vars = ansible_vars(&configs[0], &configs);
+        assert!(vars.iter().all(|(key, _)| *key != "ansible_password"));
+        assert!(to_ansible_ini(&configs).contains("ansible_password=plain"));
+    }
+}
@@ -0,0 +1,360 @@
+use std::io::{stdin, stdout, IsTerminal, Write};
+use colored::Colorize;
+use crossterm::{execute, terminal::{self, SetTitle}};
+
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::config::ServerConfig;
+use crate::ssh_module::picker::char_width;
+
+/**
+ * 对敏感服务器(danger)执行操作(登录、执行命令、传输文件等)前要求输入服务器标题确认
+ * skip 为 --yes-i-am-sure,非交互环境下必须显式指定
+ */
+pub fn confirm_dangerous(config: &ServerConfig, action: &str, skip: bool) -> Result<()> {
+    if !config.danger {
+        return Ok(());
+    }
+
+    let env = config.environment().unwrap_or("danger").to_uppercase();
+    if skip {
+        eprintln!(
+            "\n[Aspen Warning] => {}",
+            format!("已使用 --yes-i-am-sure 跳过【 {} 】({}) 的{}确认", config.title, env, action).yellow()
+        );
+        return Ok(());
+    }
+    if !stdin().is_terminal() {
+        return Err(AspenError::Usage(format!(
+            "【 {} 】为敏感服务器({}),非交互环境下需指定 --yes-i-am-sure",
+            config.title, env
+        )));
+    }
+
+    println!(
+        "\n[Aspen Warning] => 【 {} 】是 {} 环境的敏感服务器 ({}@{}:{})",
+        config.title.red().bold(), env.red().bold(), config.username, config.host, config.port
+    );
+    print!("请输入服务器标题 {} 确认{}: ", config.title.green(), action);
+    stdout().flush().map_err(|_| AspenError::Io("输出提示失败!".to_string()))?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer).map_err(|_| AspenError::Io("读取输入失败!".to_string()))?;
+    if answer.trim() != config.title {
+        eprintln!("\n{}", "输入的标题与服务器不一致".red());
+        return Err(AspenError::Cancelled);
+    }
+
+    Ok(())
+}
+
+/**
+ * 会话期间终端标题显示环境与服务器,离开作用域时恢复原标题
+ */
+pub struct SessionTitle;
+
+impl SessionTitle {
+    pub fn set(config: &ServerConfig) -> Option<SessionTitle> {
+        if !stdout().is_terminal() {
+            return None;
+        }
+
+        let title = match session_label(config) {
+            Some(label) => format!("[{}] {} - aspen", label, config.title),
+            None => format!("{} - aspen", config.title),
+        };
+
+        // 先保存原标题(xterm 标题栈),不支持的终端会忽略
+        print!("\x1b[22;0t");
+        execute!(stdout(), SetTitle(title)).ok()?;
+
+        Some(SessionTitle)
+    }
+}
+
+impl Drop for SessionTitle {
+    fn drop(&mut self) {
+        print!("\x1b[23;0t");
+        let _ = stdout().flush();
+    }
+}
+
+/**
+ * 输出整行彩色横幅,敏感服务器为红色,只设置了 env 标签时为黄色
+ * 用于无法固定横幅的场景(如 macOS 下由系统 ssh 接管终端),横幅会随输出滚走
+ */
+pub fn print_banner(config: &ServerConfig) {
+    let width = terminal::size().map(|(width, _)| width).unwrap_or(80);
+    if let Some(line) = banner_line(config, width) {
+        println!("{}", line);
+    }
+}
+
+// 整行彩色横幅
+fn banner_line(config: &ServerConfig, width: u16) -> Option<String> {
+    let label = session_label(config)?;
+
+    let text = format!(" ▲ {} ▸ {} ({}@{}:{}) ", label, config.title, config.username, config.host, config.port);
+    let text = if config.danger { format!("{}敏感服务器,请谨慎操作 ", text) } else { text };
+    let line = fit_width(&text, width as usize);
+
+    if config.danger {
+        Some(line.white().on_red().bold().to_string())
+    } else {
+        Some(line.black().on_yellow().to_string())
+    }
+}
+
+/**
+ * 会话期间固定在终端第一行的环境横幅
+ * 滚动区域(DECSTBM)把远程输出限制在第 2 行以下,远程 PTY 少一行;远程输出中的绝对行号整体下移一行,
+ * 清屏、重置终端或切换备用屏幕(vim、less 等)后重绘横幅
+ */
+pub struct PinnedBanner {
+    line: String,
+    config: ServerConfig,
+    size: (u16, u16),
+    // 被分块截断的不完整转义序列,等下一块数据到达后再处理
+    pending: Vec<u8>,
+}
+
+impl PinnedBanner {
+    /**
+     * 清屏并固定横幅,未标记 danger 且没有 env 标签、不是终端或终端太小时不固定
+     */
+    pub fn pin(config: &ServerConfig, size: (u16, u16)) -> Option<PinnedBanner> {
+        if !stdout().is_terminal() || size.1 < 3 {
+            return None;
+        }
+        let line = banner_line(config, size.0)?;
+        let banner = PinnedBanner { line, config: config.clone(), size, pending: Vec::new() };
+
+        let mut bytes = b"\x1b[2J".to_vec();
+        bytes.extend(banner.draw());
+        bytes.extend(b"\x1b[2;1H");
+        let mut out = stdout();
+        out.write_all(&bytes).and_then(|_| out.flush()).ok()?;
+
+        Some(banner)
+    }
+
+    /**
+     * 远程 PTY 的大小(列, 行),比本地终端少一行
+     */
+    pub fn pty_size(&self) -> (u16, u16) {
+        (self.size.0, self.size.1 - 1)
+    }
+
+    /**
+     * 终端大小变化后重新设置滚动区域并重绘横幅,返回要写入终端的字节
+     */
+    pub fn resize(&mut self, size: (u16, u16)) -> Vec<u8> {
+        self.size = (size.0, size.1.max(3));
+        self.line = banner_line(&self.config, self.size.0).unwrap_or_default();
+        self.draw()
+    }
+
+    /**
+     * 处理远程输出: 绝对定位的行号加一,需要时在末尾追加重绘横幅的字节
+     */
+    pub fn filter(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(data);
+
+        let mut output = Vec::with_capacity(input.len());
+        let mut redraw = false;
+        let mut index = 0;
+        while index < input.len() {
+            if input[index] != 0x1b {
+                output.push(input[index]);
+                index += 1;
+                continue;
+            }
+
+            match input.get(index + 1) {
+                None => {
+                    self.pending = input[index..].to_vec();
+                    break;
+                }
+                Some(b'[') => match csi_end(&input[index + 2..]) {
+                    Some(length) => {
+                        let sequence = &input[index..index + 2 + length];
+                        redraw |= self.rewrite_csi(sequence, &mut output);
+                        index += 2 + length;
+                    }
+                    None => {
+                        self.pending = input[index..].to_vec();
+                        break;
+                    }
+                },
+                // RIS 重置终端会清除滚动区域
+                Some(b'c') => {
+                    output.extend_from_slice(b"\x1bc");
+                    redraw = true;
+                    index += 2;
+                }
+                Some(_) => {
+                    output.push(0x1b);
+                    index += 1;
+                }
+            }
+        }
+
+        if redraw {
+            output.extend(self.draw());
+        }
+        output
+    }
+
+    // 改写一个 CSI 序列,返回之后是否需要重绘横幅
+    fn rewrite_csi(&self, sequence: &[u8], output: &mut Vec<u8>) -> bool {
+        let body = &sequence[2..sequence.len() - 1];
+        let last = sequence[sequence.len() - 1];
+        let params = std::str::from_utf8(body).ok().filter(|params| params.bytes().all(|c| c.is_ascii_digit() || c == b';'));
+
+        match (params, last) {
+            // CUP/HVP: 行;列
+            (Some(params), b'H' | b'f') => {
+                let (row, rest) = params.split_once(';').map(|(row, rest)| (row, Some(rest))).unwrap_or((params, None));
+                let row = row.parse::<u16>().unwrap_or(1).max(1) + 1;
+                match rest {
+                    Some(rest) => output.extend(format!("\x1b[{};{}{}", row, rest, last as char).bytes()),
+                    None => output.extend(format!("\x1b[{}{}", row, last as char).bytes()),
+                }
+                false
+            }
+            // VPA: 行
+            (Some(params), b'd') => {
+                let row = params.parse::<u16>().unwrap_or(1).max(1) + 1;
+                output.extend(format!("\x1b[{}d", row).bytes());
+                false
+            }
+            // DECSTBM: 上边界;下边界,省略时为整个远程屏幕
+            (Some(params), b'r') => {
+                let mut parts = params.split(';').map(|part| part.parse::<u16>().ok().filter(|value| *value > 0));
+                let top = parts.next().flatten().unwrap_or(1) + 1;
+                let bottom = parts.next().flatten().unwrap_or(self.size.1 - 1) + 1;
+                output.extend(format!("\x1b[{};{}r", top, bottom.min(self.size.1)).bytes());
+                false
+            }
+            // ED 2/3: 清除整个屏幕
+            (Some("2") | Some("3"), b'J') => {
+                output.extend_from_slice(sequence);
+                true
+            }
+            // 切换备用屏幕
+            _ if body.starts_with(b"?") && matches!(last, b'h' | b'l') => {
+                output.extend_from_slice(sequence);
+                body[1..].split(|c| *c == b';').any(|mode| matches!(mode, b"47" | b"1047" | b"1049"))
+            }
+            _ => {
+                output.extend_from_slice(sequence);
+                false
+            }
+        }
+    }
+
+    // 保存光标,设置滚动区域,在第一行输出横幅,再恢复光标
+    fn draw(&self) -> Vec<u8> {
+        format!("\x1b7\x1b[2;{}r\x1b[1;1H{}\x1b8", self.size.1, self.line).into_bytes()
+    }
+}
+
+impl Drop for PinnedBanner {
+    fn drop(&mut self) {
+        // 恢复整个屏幕为滚动区域,DECSTBM 会移动光标,先保存再恢复
+        let mut out = stdout();
+        let _ = out.write_all(b"\x1b7\x1b[r\x1b8").and_then(|_| out.flush());
+    }
+}
+
+// CSI 序列中参数与中间字节之后的结束字节位置(含结束字节的长度),数据不完整时为 None
+fn csi_end(data: &[u8]) -> Option<usize> {
+    data.iter().position(|c| (0x40..=0x7e).contains(c)).map(|position| position + 1)
+}
+
+// 按终端宽度截断或补齐,使背景色正好铺满一行
+fn fit_width(text: &str, width: usize) -> String {
+    let mut line = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        if used + char_width(c) > width {
+            break;
+        }
+        used += char_width(c);
+        line.push(c);
+    }
+    line.push_str(&" ".repeat(width - used));
+
+    line
+}
+
+// 横幅与标题中的环境名称,未标记 danger 且没有 env 标签时不显示
+fn session_label(config: &ServerConfig) -> Option<String> {
+    match (config.environment(), config.danger) {
+        (Some(env), _) => Some(env.to_uppercase()),
+        (None, true) => Some("DANGER".to_string()),
+        (None, false) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::ManuallyDrop;
+
+    // 不执行 Drop,避免向测试输出写入恢复滚动区域的序列
+    fn banner() -> ManuallyDrop<PinnedBanner> {
+        let config: ServerConfig = serde_json::from_value(serde_json::json!({
+            "title": "Web1",
+            "category": "",
+            "host": "10.0.0.1",
+            "port": 22,
+            "username": "root",
+            "remark": "",
+            "danger": true,
+        }))
+        .unwrap();
+
+        ManuallyDrop::new(PinnedBanner { line: "BANNER".to_string(), config, size: (80, 24), pending: Vec::new() })
+    }
+
+    fn filter(banner: &mut PinnedBanner, data: &[u8]) -> String {
+        String::from_utf8(banner.filter(data)).unwrap()
+    }
+
+    #[test]
+    fn absolute_rows_move_below_banner() {
+        let mut banner = banner();
+        assert_eq!(filter(&mut banner, b"a\x1b[Hb\x1b[5;10Hc\x1b[3fd\x1b[7de"), "a\x1b[2Hb\x1b[6;10Hc\x1b[4fd\x1b[8de");
+        assert_eq!(filter(&mut banner, b"\x1b[1;22r\x1b[r"), "\x1b[2;23r\x1b[2;24r");
+        // 相对移动、颜色等其他序列原样输出
+        assert_eq!(filter(&mut banner, b"\x1b[2A\x1b[31mred\x1b[0m\x1b[?25l"), "\x1b[2A\x1b[31mred\x1b[0m\x1b[?25l");
+        assert_eq!(banner.pty_size(), (80, 23));
+    }
+
+    #[test]
+    fn split_sequences_wait_for_the_rest() {
+        let mut banner = banner();
+        assert_eq!(filter(&mut banner, b"ab\x1b"), "ab");
+        assert_eq!(filter(&mut banner, b"[1"), "");
+        assert_eq!(filter(&mut banner, b"0;1Hc"), "\x1b[11;1Hc");
+    }
+
+    #[test]
+    fn clearing_the_screen_redraws_banner() {
+        let draw = "\x1b7\x1b[2;24r\x1b[1;1HBANNER\x1b8";
+        let mut banner = banner();
+        assert_eq!(filter(&mut banner, b"\x1b[2J"), format!("\x1b[2J{}", draw));
+        assert_eq!(filter(&mut banner, b"\x1b[?1049h"), format!("\x1b[?1049h{}", draw));
+        assert_eq!(filter(&mut banner, b"\x1b[?1049l\x1b[?25h"), format!("\x1b[?1049l\x1b[?25h{}", draw));
+        assert_eq!(filter(&mut banner, b"\x1bc"), format!("\x1bc{}", draw));
+        assert_eq!(filter(&mut banner, b"\x1b[J"), "\x1b[J");
+    }
+
+    #[test]
+    fn banner_fills_the_width() {
+        assert_eq!(fit_width(" ▲ PROD ", 10), " ▲ PROD   ");
+        assert_eq!(fit_width(" ▲ PROD ▸ 数据库", 13), " ▲ PROD ▸ 数 ");
+        assert_eq!(fit_width(" ▲ PROD ▸ 数据库", 14), " ▲ PROD ▸ 数据");
+    }
+}
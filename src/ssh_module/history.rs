@@ -0,0 +1,45 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::aspen_module::cli::{generate_folder, get_home_dir};
+use crate::aspen_module::error::{AspenError, Result};
+
+/**
+ * 读取服务器最近使用时间(标题 -> unix 秒),文件不存在或损坏时为空
+ */
+pub fn last_used() -> BTreeMap<String, u64> {
+    history_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/**
+ * 记录服务器的使用时间,供 all --sort last-used 排序
+ * 写入失败不影响链接,直接忽略
+ */
+pub fn record_usage(title: &str) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+
+    let mut history = last_used();
+    history.insert(title.to_string(), now);
+
+    let _ = history_path().and_then(|path| {
+        let contents = serde_json::to_string_pretty(&history)
+            .map_err(|_| AspenError::Config("错误的使用记录!".to_string()))?;
+        fs::write(path, contents).map_err(|_| AspenError::Io("写入使用记录失败!".to_string()))
+    });
+}
+
+fn history_path() -> Result<PathBuf> {
+    let dir = env!("CARGO_PKG_NAME");
+    let config_dir = format!("{}/{}/config", get_home_dir()?.to_str().unwrap(), dir);
+    generate_folder(config_dir.clone())?;
+
+    Ok(PathBuf::from(config_dir).join("last_used.json"))
+}
@@ -0,0 +1,271 @@
+use std::{env, path::{Path, PathBuf}};
+use clap::ArgMatches;
+use colored::Colorize;
+use prettytable::{row, Table};
+
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::command::table_format;
+use crate::ssh_module::config::{expand_home, get_servers_path, load_sources, write_server_config, ServerConfig};
+use crate::ssh_module::manage::{empty_server, load_servers, prompt_line};
+use crate::ssh_module::ssh_config::{parse_ssh_config, SshHost};
+
+// 导入时对每台主机的处理
+enum ImportAction {
+    Add,
+    Update(usize),
+    Skip,
+}
+
+// 一台待导入的主机,chain 为 ProxyJump 各跳对应的服务器标题
+struct Planned {
+    action: ImportAction,
+    config: ServerConfig,
+    chain: Vec<String>,
+    notes: Vec<String>,
+}
+
+/**
+ * import 命令实现
+ */
+pub fn impl_import_action(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("ssh-config", sub_matches)) => import_ssh_config_action(sub_matches),
+        _ => Err(AspenError::Usage("非法指令".to_string())),
+    }
+}
+
+// 从 OpenSSH 配置文件导入服务器,已存在的同名服务器默认跳过,--update 时更新链接信息
+fn import_ssh_config_action(matches: &ArgMatches) -> Result<()> {
+    let source = match matches.get_one::<String>("path") {
+        Some(path) => expand_home(path),
+        None => expand_home("~/.ssh/config"),
+    };
+    let update = matches.get_flag("update");
+
+    let hosts = parse_ssh_config(&source).map_err(AspenError::Config)?;
+    if hosts.is_empty() {
+        return Err(AspenError::NotFound(format!("{} 中没有可导入的主机(通配符 Host 不会导入)!", source.display())));
+    }
+
+    // 与全部配置文件中的服务器对照,新增的服务器写入 get_servers_path 对应的文件
+    let path = get_servers_path()?;
+    let configs = load_sources(true)?;
+
+    let mut planned: Vec<Planned> = Vec::new();
+    for host in &hosts {
+        let existing = find_by_name(&configs, &host.name);
+        let action = match existing {
+            Some(index) if update => ImportAction::Update(index),
+            Some(_) => ImportAction::Skip,
+            None => ImportAction::Add,
+        };
+
+        let mut notes: Vec<String> = Vec::new();
+        let mut config = to_server_config(host, &source, &configs, existing, &mut notes);
+        let chain = resolve_chain(&host.proxy_jump, &configs, &hosts, &mut notes);
+        if let Some(jump) = chain.last() {
+            config.proxy_jump = Some(jump.clone());
+        }
+        planned.push(Planned { action, config, chain, notes });
+    }
+    link_jump_chains(&mut planned, &configs);
+
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(row!["Action", "Title", "Host", "Port", "User", "Identity", "ProxyJump", "Note"]);
+    for Planned { action, config, notes, .. } in &planned {
+        let name = match action {
+            ImportAction::Add => "新增".green(),
+            ImportAction::Update(_) => "更新".yellow(),
+            ImportAction::Skip => "跳过".dimmed(),
+        };
+        table.add_row(row![
+            name, config.title, config.host, config.port, config.username,
+            config.identity_file.as_deref().unwrap_or("-"), config.proxy_jump.as_deref().unwrap_or("-"), notes.join("; ")
+        ]);
+    }
+
+    println!();
+    table.printstd();
+
+    let (added, updated) = planned.iter().fold((0, 0), |(added, updated), planned| match planned.action {
+        ImportAction::Add => (added + 1, updated),
+        ImportAction::Update(_) => (added, updated + 1),
+        ImportAction::Skip => (added, updated),
+    });
+    let skipped = planned.len() - added - updated;
+    println!("\n新增 {} 台, 更新 {} 台, 跳过 {} 台{}", added, updated, skipped, if skipped > 0 && !update { "(使用 --update 更新已存在的服务器)" } else { "" });
+
+    if added + updated == 0 || matches.get_flag("dry-run") {
+        return Ok(());
+    }
+    if !matches.get_flag("yes") {
+        let answer = prompt_line(&format!("\n确认写入配置文件? 输入 {} 确认", "yes".green()), None)?;
+        if !answer.eq_ignore_ascii_case("yes") {
+            return Err(AspenError::Cancelled);
+        }
+    }
+
+    // 先写入新增的服务器,更新的服务器可能以它们作为跳板机
+    let mut files: Vec<(PathBuf, Vec<ServerConfig>)> = Vec::new();
+    if added > 0 {
+        files.push((path.clone(), load_servers(&path)?));
+    }
+    for Planned { action, config, .. } in planned {
+        let (file, title) = match action {
+            ImportAction::Add => (path.clone(), None),
+            ImportAction::Update(index) => (configs[index].source.clone(), Some(configs[index].title.clone())),
+            ImportAction::Skip => continue,
+        };
+        let position = match files.iter().position(|(other, _)| *other == file) {
+            Some(position) => position,
+            None => {
+                let servers = load_servers(&file)?;
+                files.push((file, servers));
+                files.len() - 1
+            }
+        };
+
+        let servers = &mut files[position].1;
+        match title.and_then(|title| servers.iter().position(|server| server.title == title)) {
+            Some(index) => servers[index] = config,
+            None => servers.push(config),
+        }
+    }
+    for (file, servers) in &files {
+        write_server_config(file, servers)?;
+    }
+
+    println!("\n[Aspen Success] ==> 已从 {} 导入 {} 台服务器\n", source.display(), (added + updated).to_string().green());
+    Ok(())
+}
+
+// 按标题或别名查找,不把纯数字当作序号
+fn find_by_name(configs: &[ServerConfig], name: &str) -> Option<usize> {
+    configs.iter().position(|config| config.title == name || config.aliases.iter().any(|alias| alias == name))
+}
+
+// 转为服务器配置,更新时保留原有的密码、标签、端口转发等字段
+fn to_server_config(
+    host: &SshHost,
+    source: &Path,
+    configs: &[ServerConfig],
+    existing: Option<usize>,
+    notes: &mut Vec<String>,
+) -> ServerConfig {
+    let mut config = match existing {
+        Some(index) => configs[index].clone(),
+        None => {
+            let mut config = empty_server();
+            config.title = host.name.clone();
+            config.remark = format!("导入自 {}", source.display());
+            // ssh 配置中的主机通常依赖 ssh-agent 或默认私钥
+            config.use_agent = true;
+            config
+        }
+    };
+
+    config.host = host.host_name.clone();
+    config.port = host.port;
+    if let Some(user) = &host.user {
+        config.username = user.clone();
+    } else if config.username.is_empty() {
+        config.username = env::var("USER").unwrap_or_else(|_| "root".to_string());
+        notes.push("未配置 User,使用当前用户".to_string());
+    }
+    if host.identity_file.is_some() {
+        config.identity_file = host.identity_file.clone();
+    }
+
+    // 别名不能与其他服务器的标题或别名冲突
+    for alias in &host.aliases {
+        let taken = configs
+            .iter()
+            .enumerate()
+            .any(|(index, other)| Some(index) != existing && (other.title == *alias || other.aliases.contains(alias)));
+        if taken || alias.parse::<usize>().is_ok() {
+            notes.push(format!("别名 {} 为纯数字或已被占用,已忽略", alias));
+        } else if !config.aliases.contains(alias) {
+            config.aliases.push(alias.clone());
+        }
+    }
+
+    config
+}
+
+// 把 ProxyJump 的各跳转为服务器标题,有一跳找不到时整条链都忽略
+fn resolve_chain(hops: &[String], configs: &[ServerConfig], hosts: &[SshHost], notes: &mut Vec<String>) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+    for hop in hops {
+        match resolve_jump(hop, configs, hosts) {
+            Some(title) => chain.push(title),
+            None => {
+                notes.push(format!("跳板机 {} 不在服务器列表中,已忽略", hop));
+                return Vec::new();
+            }
+        }
+    }
+
+    chain
+}
+
+// 多跳 ProxyJump(a,b) 中服务器只记录最后一跳 b,b 需经 a 链接: 本次导入的 b 没有跳板机时设为 a,
+// 否则(b 已有其他跳板机,或会形成循环)提示多跳链被截断
+fn link_jump_chains(planned: &mut [Planned], configs: &[ServerConfig]) {
+    for index in 0..planned.len() {
+        let chain = planned[index].chain.clone();
+        for pair in chain.windows(2) {
+            let (previous, hop) = (&pair[0], &pair[1]);
+            let target = planned
+                .iter()
+                .position(|other| other.config.title == *hop && !matches!(other.action, ImportAction::Skip));
+
+            match (target, jump_of(planned, configs, hop)) {
+                (_, Some(jump)) if jump == *previous => {}
+                (Some(target), None) if !jumps_through(planned, configs, previous, hop) => {
+                    planned[target].config.proxy_jump = Some(previous.clone());
+                    planned[target].notes.push(format!("多跳链 {} 经过此服务器,跳板机设为 {}", chain.join(","), previous));
+                }
+                _ => planned[index].notes.push(format!("{} 的跳板机不是 {},多跳链 {} 被截断", hop, previous, chain.join(","))),
+            }
+        }
+    }
+}
+
+// 服务器的跳板机,本次导入的配置优先
+fn jump_of(planned: &[Planned], configs: &[ServerConfig], title: &str) -> Option<String> {
+    match planned.iter().find(|other| other.config.title == title && !matches!(other.action, ImportAction::Skip)) {
+        Some(other) => other.config.proxy_jump.clone(),
+        None => configs.iter().find(|config| config.title == title).and_then(|config| config.proxy_jump.clone()),
+    }
+}
+
+// 从 start 沿跳板机向前是否会经过 title
+fn jumps_through(planned: &[Planned], configs: &[ServerConfig], start: &str, title: &str) -> bool {
+    let mut current = Some(start.to_string());
+    for _ in 0..=planned.len() + configs.len() {
+        match current {
+            Some(ref name) if name == title => return true,
+            Some(ref name) => current = jump_of(planned, configs, name),
+            None => return false,
+        }
+    }
+
+    true
+}
+
+// 跳板机可能是本次导入的主机,也可能是已有的服务器(按标题、别名或主机地址)
+fn resolve_jump(jump: &str, configs: &[ServerConfig], hosts: &[SshHost]) -> Option<String> {
+    if let Some(host) = hosts.iter().find(|host| host.name == jump || host.aliases.iter().any(|alias| alias == jump)) {
+        return Some(host.name.clone());
+    }
+    if let Some(index) = find_by_name(configs, jump) {
+        return Some(configs[index].title.clone());
+    }
+
+    hosts
+        .iter()
+        .find(|host| host.host_name == jump)
+        .map(|host| host.name.clone())
+        .or_else(|| configs.iter().find(|config| config.host == jump).map(|config| config.title.clone()))
+}
@@ -0,0 +1,545 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{stdin, IsTerminal, Write},
+    path::PathBuf,
+};
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+    Engine,
+};
+use colored::Colorize;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use ssh2::Session;
+
+use crate::aspen_module::cli::get_home_dir;
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::config::ServerConfig;
+
+// known_hosts 文件中的一条记录
+#[derive(Debug, Clone)]
+pub struct KnownHostEntry {
+    // 记录所在行号(从 1 开始)
+    pub line: usize,
+    // @revoked / @cert-authority 标记
+    pub marker: Option<String>,
+    pub hosts: String,
+    pub key_type: String,
+    pub key: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HostKeyStatus {
+    Match,
+    Mismatch,
+    Revoked,
+    NotFound,
+}
+
+impl KnownHostEntry {
+    /**
+     * 记录是否适用于指定的主机和端口(支持哈希、通配符与否定形式)
+     */
+    pub fn matches(&self, host: &str, port: u32) -> bool {
+        let name = host_entry_name(host, port);
+        let mut matched = false;
+
+        for pattern in self.hosts.split(',') {
+            if let Some(hashed) = pattern.strip_prefix("|1|") {
+                if hashed_host_matches(hashed, &name) {
+                    matched = true;
+                }
+            } else if let Some(negated) = pattern.strip_prefix('!') {
+                if wildcard_match(negated, &name) {
+                    return false;
+                }
+            } else if wildcard_match(pattern, &name) {
+                matched = true;
+            }
+        }
+
+        matched
+    }
+
+    /**
+     * 公钥的 SHA256 指纹
+     */
+    pub fn fingerprint(&self) -> String {
+        match STANDARD.decode(&self.key) {
+            Ok(blob) => fingerprint(&blob),
+            Err(_) => "-".to_string(),
+        }
+    }
+}
+
+/**
+ * known_hosts 文件地址
+ */
+pub fn known_hosts_path() -> Result<PathBuf> {
+    Ok(get_home_dir()?.join(".ssh").join("known_hosts"))
+}
+
+/**
+ * 读取 known_hosts 中的全部记录
+ */
+pub fn read_known_hosts() -> Vec<KnownHostEntry> {
+    match known_hosts_path().ok().and_then(|path| fs::read_to_string(path).ok()) {
+        Some(content) => parse_known_hosts(&content),
+        None => Vec::new(),
+    }
+}
+
+/**
+ * 解析 known_hosts 内容,忽略注释与无法识别的行
+ */
+pub fn parse_known_hosts(content: &str) -> Vec<KnownHostEntry> {
+    let mut entries = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let mut first = fields.next();
+        let marker = match first {
+            Some(field) if field.starts_with('@') => {
+                first = fields.next();
+                Some(field.to_string())
+            }
+            _ => None,
+        };
+
+        if let (Some(hosts), Some(key_type), Some(key)) = (first, fields.next(), fields.next()) {
+            entries.push(KnownHostEntry {
+                line: index + 1,
+                marker,
+                hosts: hosts.to_string(),
+                key_type: key_type.to_string(),
+                key: key.to_string(),
+            });
+        }
+    }
+
+    entries
+}
+
+/**
+ * 校验服务器公钥: 优先使用配置中固定的指纹,其次对照 known_hosts
+ */
+pub fn verify_host_key(sess: &Session, config: &ServerConfig) -> Result<()> {
+    let blob = match sess.host_key() {
+        Some((blob, _)) => blob.to_vec(),
+        None => return Err(AspenError::HostKey("无法获取主机公钥!".to_string())),
+    };
+
+    let actual = fingerprint(&blob);
+    let status = check_host_key(config, &blob);
+
+    if let Some(pinned) = &config.host_key_fingerprint {
+        if normalize_fingerprint(pinned) != normalize_fingerprint(&actual) {
+            return Err(AspenError::HostKey(format!(
+                "【 {} 】的主机公钥与 host_key_fingerprint 不一致,已拒绝链接!\n  期望指纹: {}\n  实际指纹: {}",
+                config.title, pinned, actual
+            )));
+        }
+
+        // 固定指纹校验通过视为可信,同步写入 known_hosts 供系统 ssh 使用
+        if status == HostKeyStatus::NotFound {
+            append_host_key(&config.host, config.port, &blob)?;
+        }
+        return Ok(());
+    }
+
+    match status {
+        HostKeyStatus::Match => Ok(()),
+        HostKeyStatus::Mismatch | HostKeyStatus::Revoked => Err(AspenError::HostKey(format!(
+            "【 {} 】的主机公钥与 known_hosts 中的记录不一致,可能存在中间人攻击,已拒绝链接!\n  当前指纹: {}\n  如确认服务器已更换密钥,请执行: aspentool known-hosts accept \"{}\"",
+            config.title, actual, config.title
+        ))),
+        HostKeyStatus::NotFound => {
+            if !confirm_unknown_host(config, &blob, &actual) {
+                return Err(AspenError::HostKey("主机公钥未被信任,已取消链接!".to_string()));
+            }
+
+            append_host_key(&config.host, config.port, &blob)
+        }
+    }
+}
+
+/**
+ * 非交互地校验主机公钥,未被信任(未记录、不一致或已吊销)时返回错误
+ */
+pub fn check_trusted_host_key(sess: &Session, config: &ServerConfig) -> Result<()> {
+    let (blob, _) = sess.host_key().ok_or_else(|| AspenError::HostKey("无法获取主机公钥!".to_string()))?;
+    let actual = fingerprint(blob);
+
+    if let Some(pinned) = &config.host_key_fingerprint {
+        if normalize_fingerprint(pinned) != normalize_fingerprint(&actual) {
+            return Err(AspenError::HostKey(format!("主机公钥与 host_key_fingerprint 不一致 ({})", actual)));
+        }
+        return Ok(());
+    }
+
+    match check_host_key(config, blob) {
+        HostKeyStatus::Match => Ok(()),
+        HostKeyStatus::Mismatch | HostKeyStatus::Revoked => {
+            Err(AspenError::HostKey(format!("主机公钥与 known_hosts 中的记录不一致 ({})", actual)))
+        }
+        HostKeyStatus::NotFound => Err(AspenError::HostKey(format!(
+            "主机公钥未被信任,请先执行: aspentool known-hosts accept \"{}\"", config.title
+        ))),
+    }
+}
+
+/**
+ * 对照 known_hosts 检查主机公钥
+ */
+pub fn check_host_key(config: &ServerConfig, blob: &[u8]) -> HostKeyStatus {
+    host_key_status(&read_known_hosts(), &config.host, config.port, blob)
+}
+
+// 主机已记录其他公钥(包括只记录了其他类型的公钥)时视为不一致,
+// 避免攻击者改用另一种密钥类型绕过校验,而不是按首次链接询问是否信任
+fn host_key_status(entries: &[KnownHostEntry], host: &str, port: u32, blob: &[u8]) -> HostKeyStatus {
+    let key = STANDARD.encode(blob);
+    let mut status = HostKeyStatus::NotFound;
+
+    for entry in entries {
+        if entry.marker.as_deref() == Some("@cert-authority") || !entry.matches(host, port) {
+            continue;
+        }
+
+        if entry.marker.as_deref() == Some("@revoked") {
+            if entry.key == key {
+                return HostKeyStatus::Revoked;
+            }
+            continue;
+        }
+
+        if entry.key == key {
+            status = HostKeyStatus::Match;
+        } else if status != HostKeyStatus::Match {
+            status = HostKeyStatus::Mismatch;
+        }
+    }
+
+    status
+}
+
+/**
+ * 追加一条记录到 known_hosts
+ */
+pub fn append_host_key(host: &str, port: u32, blob: &[u8]) -> Result<()> {
+    let type_name = key_type_name(blob)
+        .ok_or_else(|| AspenError::HostKey("不支持的主机公钥类型!".to_string()))?;
+
+    let path = known_hosts_path()?;
+    if let Some(dir) = path.parent() {
+        if !dir.exists() && fs::create_dir_all(dir).is_err() {
+            return Err(AspenError::Io("创建 ~/.ssh 目录失败!".to_string()));
+        }
+    }
+
+    // 保证追加的记录独占一行
+    let needs_newline = fs::read(&path).map(|c| !c.is_empty() && !c.ends_with(b"\n")).unwrap_or(false);
+    let line = format!(
+        "{}{} {} {}\n",
+        if needs_newline { "\n" } else { "" },
+        host_entry_name(host, port), type_name, STANDARD.encode(blob)
+    );
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|_| AspenError::Io("写入 known_hosts 失败!".to_string()))
+}
+
+/**
+ * 从 known_hosts 删除指定主机的全部记录,返回删除的数量
+ */
+pub fn remove_host_keys(host: &str, port: u32) -> Result<usize> {
+    let path = known_hosts_path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return Ok(0),
+    };
+
+    let removed_lines: Vec<usize> = parse_known_hosts(&content)
+        .into_iter()
+        // 通配符记录可能覆盖其他主机,不随单个主机删除
+        .filter(|entry| entry.marker.is_none() && !entry.hosts.contains(['*', '?']) && entry.matches(host, port))
+        .map(|entry| entry.line)
+        .collect();
+
+    if removed_lines.is_empty() {
+        return Ok(0);
+    }
+
+    let mut kept: String = content
+        .lines()
+        .enumerate()
+        .filter(|(index, _)| !removed_lines.contains(&(index + 1)))
+        .map(|(_, line)| line)
+        .collect::<Vec<&str>>()
+        .join("\n");
+    if !kept.is_empty() {
+        kept.push('\n');
+    }
+
+    if fs::write(&path, kept).is_err() {
+        return Err(AspenError::Io("写入 known_hosts 失败!".to_string()));
+    }
+
+    Ok(removed_lines.len())
+}
+
+/**
+ * OpenSSH 格式的 SHA256 指纹
+ */
+pub fn fingerprint(blob: &[u8]) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(blob)))
+}
+
+/**
+ * known_hosts 中的公钥类型名称,取自公钥数据开头的算法名(4 字节大端长度 + 名称)
+ * 不依赖 ssh2::HostKeyType,其 ed25519 变体名称在不同版本中不一致
+ */
+pub fn key_type_name(blob: &[u8]) -> Option<&str> {
+    let length = u32::from_be_bytes(blob.get(..4)?.try_into().ok()?) as usize;
+    let name = std::str::from_utf8(blob.get(4..4usize.checked_add(length)?)?).ok()?;
+
+    Some(name).filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_graphic()))
+}
+
+// 首次链接时询问是否信任主机公钥,非交互环境直接拒绝
+fn confirm_unknown_host(config: &ServerConfig, blob: &[u8], fingerprint: &str) -> bool {
+    let type_name = key_type_name(blob).unwrap_or("unknown");
+
+    if !stdin().is_terminal() {
+        eprintln!(
+            "\n[Aspen Error] => {}\n  {} 指纹: {}\n  请先执行: aspentool known-hosts accept \"{}\"\n",
+            format!("主机【 {} 】不在 known_hosts 中!", config.title).red(),
+            type_name, fingerprint, config.title
+        );
+        return false;
+    }
+
+    println!(
+        "\n[Aspen Waiting] ==> 主机【 {} 】({}) 不在 known_hosts 中\n  {} 指纹: {}",
+        config.title.green(), host_entry_name(&config.host, config.port), type_name, fingerprint.yellow()
+    );
+    println!("请确认指纹无误后输入 {} 信任该主机:", "yes".green());
+
+    let mut answer = String::new();
+    if stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    answer.trim().eq_ignore_ascii_case("yes")
+}
+
+// known_hosts 中的主机名,非 22 端口使用 [host]:port 的形式
+fn host_entry_name(host: &str, port: u32) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+// 哈希形式的主机名: |1|base64(salt)|base64(HMAC-SHA1(salt, host))
+fn hashed_host_matches(hashed: &str, name: &str) -> bool {
+    let (salt, hash) = match hashed.split_once('|') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    let (salt, hash) = match (STANDARD.decode(salt), STANDARD.decode(hash)) {
+        (Ok(salt), Ok(hash)) => (salt, hash),
+        _ => return false,
+    };
+
+    let mut mac = match Hmac::<Sha1>::new_from_slice(&salt) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(name.as_bytes());
+
+    mac.verify_slice(&hash).is_ok()
+}
+
+/**
+ * 支持 * 与 ? 的通配符匹配(不区分大小写)
+ */
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn normalize_fingerprint(value: &str) -> String {
+    value.trim().trim_start_matches("SHA256:").trim_end_matches('=').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // HMAC-SHA1,salt 为 0x00..0x13,由 OpenSSH 的哈希算法独立计算得到
+    const HASHED_EXAMPLE: &str = "|1|AAECAwQFBgcICQoLDA0ODxAREhM=|nnUK16ANsXd3hL31YfAkGOluSjU=";
+    const HASHED_EXAMPLE_2222: &str = "|1|AAECAwQFBgcICQoLDA0ODxAREhM=|Wgcx+Fm+LmaWwC7rQ80eIf2uHe0=";
+
+    fn entry(hosts: &str) -> KnownHostEntry {
+        KnownHostEntry {
+            line: 1,
+            marker: None,
+            hosts: hosts.to_string(),
+            key_type: "ssh-ed25519".to_string(),
+            key: String::new(),
+        }
+    }
+
+    // 4 字节大端长度 + 算法名 + 任意公钥数据
+    fn key_blob(name: &str) -> Vec<u8> {
+        let mut blob = (name.len() as u32).to_be_bytes().to_vec();
+        blob.extend_from_slice(name.as_bytes());
+        blob.extend_from_slice(&[0, 0, 0, 1, 42]);
+        blob
+    }
+
+    #[test]
+    fn parses_entries_markers_and_skips_noise() {
+        let content = "\
+# comment
+example.com,10.0.0.1 ssh-ed25519 AAAAkey1 user@host
+
+@revoked bad.example.com ssh-rsa AAAAkey2
+@cert-authority *.example.com ssh-rsa AAAAkey3
+incomplete-line ssh-rsa
+[example.com]:2222 ecdsa-sha2-nistp256 AAAAkey4
+";
+        let entries = parse_known_hosts(content);
+
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].line, 2);
+        assert_eq!(entries[0].hosts, "example.com,10.0.0.1");
+        assert_eq!(entries[0].key_type, "ssh-ed25519");
+        assert_eq!(entries[0].key, "AAAAkey1");
+        assert_eq!(entries[0].marker, None);
+        assert_eq!(entries[1].marker.as_deref(), Some("@revoked"));
+        assert_eq!(entries[1].hosts, "bad.example.com");
+        assert_eq!(entries[2].marker.as_deref(), Some("@cert-authority"));
+        assert_eq!(entries[3].line, 7);
+        assert_eq!(entries[3].hosts, "[example.com]:2222");
+    }
+
+    #[test]
+    fn hashed_hosts_match_only_the_hashed_name() {
+        let hashed = HASHED_EXAMPLE.strip_prefix("|1|").unwrap();
+
+        assert!(hashed_host_matches(hashed, "example.com"));
+        assert!(!hashed_host_matches(hashed, "example.org"));
+        assert!(!hashed_host_matches(hashed, "[example.com]:2222"));
+        assert!(!hashed_host_matches("not-base64|???", "example.com"));
+        assert!(!hashed_host_matches("missing-separator", "example.com"));
+
+        assert!(entry(HASHED_EXAMPLE).matches("example.com", 22));
+        assert!(!entry(HASHED_EXAMPLE).matches("example.com", 2222));
+        assert!(entry(HASHED_EXAMPLE_2222).matches("example.com", 2222));
+    }
+
+    #[test]
+    fn wildcards_match_case_insensitively() {
+        assert!(wildcard_match("*.example.com", "web.EXAMPLE.com"));
+        assert!(wildcard_match("web?", "web1"));
+        assert!(!wildcard_match("web?", "web12"));
+        assert!(wildcard_match("10.0.*.1", "10.0.200.1"));
+        assert!(!wildcard_match("10.0.*.1", "10.0.200.2"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("", "a"));
+        assert!(wildcard_match("a*b*c", "aXXbYYc"));
+    }
+
+    #[test]
+    fn entries_respect_ports_and_negation() {
+        assert!(entry("example.com").matches("example.com", 22));
+        assert!(!entry("example.com").matches("example.com", 2222));
+        assert!(entry("[example.com]:2222").matches("example.com", 2222));
+        assert!(!entry("[example.com]:2222").matches("example.com", 22));
+        assert!(entry("[*.example.com]:*").matches("web.example.com", 2200));
+
+        // 否定形式优先,即使其他模式匹配也不适用
+        assert!(entry("*.example.com,!db.example.com").matches("web.example.com", 22));
+        assert!(!entry("*.example.com,!db.example.com").matches("db.example.com", 22));
+        assert!(!entry("!db.example.com").matches("web.example.com", 22));
+    }
+
+    #[test]
+    fn key_type_is_read_from_blob() {
+        assert_eq!(key_type_name(&key_blob("ssh-ed25519")), Some("ssh-ed25519"));
+        assert_eq!(key_type_name(&key_blob("ecdsa-sha2-nistp256")), Some("ecdsa-sha2-nistp256"));
+        assert_eq!(key_type_name(&[0, 0, 0, 20, b's']), None);
+        assert_eq!(key_type_name(&[0, 0]), None);
+        assert_eq!(key_type_name(&[255, 255, 255, 255]), None);
+    }
+
+    #[test]
+    fn host_key_status_follows_known_hosts() {
+        let ed25519 = key_blob("ssh-ed25519");
+        let rsa = key_blob("ssh-rsa");
+        let line = |hosts: &str, blob: &[u8]| {
+            format!("{} {} {}\n", hosts, key_type_name(blob).unwrap(), STANDARD.encode(blob))
+        };
+        let status = |content: &str| host_key_status(&parse_known_hosts(content), "example.com", 22, &ed25519);
+
+        assert_eq!(status(""), HostKeyStatus::NotFound);
+        assert_eq!(status(&line("other.com", &rsa)), HostKeyStatus::NotFound);
+        assert_eq!(status(&line("example.com", &ed25519)), HostKeyStatus::Match);
+        assert_eq!(status(&(line("example.com", &rsa) + &line("example.com", &ed25519))), HostKeyStatus::Match);
+        assert_eq!(status(&format!("@revoked {}", line("example.com", &ed25519))), HostKeyStatus::Revoked);
+        assert_eq!(status(&format!("@cert-authority {}", line("example.com", &rsa))), HostKeyStatus::NotFound);
+
+        let mut other_ed25519 = ed25519.clone();
+        *other_ed25519.last_mut().unwrap() = 43;
+        assert_eq!(status(&line("example.com", &other_ed25519)), HostKeyStatus::Mismatch);
+    }
+
+    #[test]
+    fn host_known_only_under_other_key_types_is_a_mismatch() {
+        let ed25519 = key_blob("ssh-ed25519");
+        let entries = parse_known_hosts(&format!("example.com ssh-rsa {}\n", STANDARD.encode(key_blob("ssh-rsa"))));
+
+        assert_eq!(host_key_status(&entries, "example.com", 22, &ed25519), HostKeyStatus::Mismatch);
+        assert_eq!(host_key_status(&entries, "example.com", 2222, &ed25519), HostKeyStatus::NotFound);
+    }
+
+    #[test]
+    fn fingerprints_are_compared_without_prefix_and_padding() {
+        assert_eq!(normalize_fingerprint(" SHA256:abc= "), normalize_fingerprint("abc"));
+        assert_eq!(fingerprint(b"").len(), "SHA256:".len() + 43);
+    }
+}
@@ -0,0 +1,332 @@
+use std::{cmp::Reverse, collections::BTreeMap, io::{self, stdout, IsTerminal, Write}};
+use clap::ArgMatches;
+use colored::Colorize;
+use prettytable::{format, Cell, Row, Table};
+use serde_json::{Map, Value};
+
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::command::{clear_terminal, display_path, table_format};
+use crate::ssh_module::config::{get_config, ServerConfig};
+use crate::ssh_module::export::csv_field;
+use crate::ssh_module::history::last_used;
+use crate::ssh_module::selector::Selector;
+
+// 可选的列: (名称, 表格标题)
+const COLUMNS: [(&str, &str); 16] = [
+    ("id", "ID"),
+    ("title", "Title"),
+    ("host", "Host"),
+    ("port", "Port"),
+    ("category", "Server Type"),
+    ("username", "Username"),
+    ("password", "Password"),
+    ("remark", "Remark"),
+    ("source", "Source"),
+    ("aliases", "Aliases"),
+    ("tags", "Tags"),
+    ("labels", "Labels"),
+    ("proxy_jump", "ProxyJump"),
+    ("identity_file", "Identity"),
+    ("use_agent", "Agent"),
+    ("danger", "Danger"),
+];
+
+// 未指定 --columns 时的列,与原有表格一致
+const DEFAULT_COLUMNS: [&str; 8] = ["id", "title", "host", "port", "category", "username", "remark", "source"];
+
+// 不显示密码时的占位
+const MASKED: &str = "******";
+
+/**
+ * all 命令实现: 以表格或 json、csv、tsv、plain 等便于脚本处理的格式输出服务器列表
+ * 支持按 分类、主机、标签 筛选,排序,以及按分类分组
+ */
+pub fn impl_servers_table_action(matches: &ArgMatches) -> Result<()> {
+    let configs = get_config()?;
+    let columns = parse_columns(matches.get_one::<String>("columns"))?;
+    let show_secrets = matches.get_flag("show-secrets");
+    let filter = Selector::from_filter_args(matches)?;
+
+    // 筛选、排序后仍保留原有序号作为 ID,与 ssh 等命令使用的序号一致
+    let mut servers: Vec<(usize, &ServerConfig)> = configs.iter().enumerate().filter(|(_, config)| filter.matches(config)).collect();
+    if let Some(sort) = matches.get_one::<String>("sort") {
+        sort_servers(&mut servers, sort);
+    }
+
+    let groups: Vec<(Option<&str>, Vec<Vec<Value>>)> = group_servers(&servers, matches.get_one::<String>("group-by").is_some())
+        .into_iter()
+        .map(|(category, servers)| {
+            let rows = servers
+                .iter()
+                .map(|(index, config)| columns.iter().map(|column| column_value(config, *index, column, show_secrets)).collect())
+                .collect();
+            (category, rows)
+        })
+        .collect();
+    let rows: Vec<Vec<Value>> = groups.iter().flat_map(|(_, rows)| rows.iter().cloned()).collect();
+    let grouped = groups.iter().any(|(category, _)| category.is_some());
+
+    let content = match matches.get_one::<String>("format").unwrap().as_str() {
+        "json" if grouped => {
+            let object: Map<String, Value> = groups
+                .iter()
+                .map(|(category, rows)| (category.unwrap_or_default().to_string(), to_json_value(&columns, rows)))
+                .collect();
+            serde_json::to_string_pretty(&object).unwrap() + "\n"
+        }
+        "json" => serde_json::to_string_pretty(&to_json_value(&columns, &rows)).unwrap() + "\n",
+        // 分组时 csv、tsv、plain 中同一分类的行相邻
+        "csv" => to_delimited(&columns, &rows, ","),
+        "tsv" => to_delimited(&columns, &rows, "\t"),
+        "plain" => to_table(&columns, &rows, format::FormatBuilder::new().column_separator(' ').padding(0, 1).build(), false),
+        _ => {
+            // 输出到管道或文件时不清屏
+            if !matches.get_flag("no-clear") && stdout().is_terminal() {
+                clear_terminal();
+            }
+
+            if grouped {
+                groups
+                    .iter()
+                    .map(|(category, rows)| {
+                        let name = match category.unwrap_or_default() {
+                            "" => "未分类",
+                            category => category,
+                        };
+                        format!("\n▸ {} ({})\n{}", name.yellow(), rows.len(), to_table(&columns, rows, table_format(), true))
+                    })
+                    .collect()
+            } else {
+                to_table(&columns, &rows, table_format(), true)
+            }
+        }
+    };
+
+    // 下游(如 head、fzf)提前关闭管道时正常结束
+    match stdout().lock().write_all(content.as_bytes()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(AspenError::Io(format!("输出服务器列表失败: {}", e))),
+        _ => Ok(()),
+    }
+}
+
+// 稳定排序,名称相同时保持原有顺序; last-used 按最近使用时间倒序,未使用过的排在最后
+fn sort_servers(servers: &mut [(usize, &ServerConfig)], sort: &str) {
+    match sort {
+        "title" => servers.sort_by_key(|(_, config)| config.title.to_lowercase()),
+        "host" => servers.sort_by_key(|(_, config)| config.host.to_lowercase()),
+        "category" => servers.sort_by_key(|(_, config)| config.category.to_lowercase()),
+        "last-used" => sort_by_last_used(servers, &last_used()),
+        _ => {}
+    }
+}
+
+// history 为服务器标题到最近使用时间的对应
+fn sort_by_last_used(servers: &mut [(usize, &ServerConfig)], history: &BTreeMap<String, u64>) {
+    servers.sort_by_key(|(_, config)| Reverse(history.get(&config.title).copied()));
+}
+
+// 一组服务器: (分类, [(原有序号, 服务器)]),不分组时分类为 None
+type Group<'a> = (Option<&'a str>, Vec<(usize, &'a ServerConfig)>);
+
+// 按分类首次出现的顺序分组,不分组时整体为一组
+fn group_servers<'a>(servers: &[(usize, &'a ServerConfig)], by_category: bool) -> Vec<Group<'a>> {
+    if !by_category {
+        return vec![(None, servers.to_vec())];
+    }
+
+    let mut groups: Vec<Group> = Vec::new();
+    for (index, config) in servers {
+        match groups.iter_mut().find(|(category, _)| *category == Some(config.category.as_str())) {
+            Some((_, members)) => members.push((*index, config)),
+            None => groups.push((Some(config.category.as_str()), vec![(*index, config)])),
+        }
+    }
+
+    groups
+}
+
+// 逗号分隔的列名,忽略大小写,- 与 _ 等价
+fn parse_columns(value: Option<&String>) -> Result<Vec<&'static str>> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(DEFAULT_COLUMNS.to_vec()),
+    };
+
+    let mut columns: Vec<&'static str> = Vec::new();
+    for name in value.split(',').map(|name| name.trim().to_lowercase().replace('-', "_")).filter(|name| !name.is_empty()) {
+        match COLUMNS.iter().find(|(column, _)| *column == name) {
+            Some((column, _)) => columns.push(column),
+            None => {
+                let names: Vec<&str> = COLUMNS.iter().map(|(column, _)| *column).collect();
+                return Err(AspenError::Usage(format!("未知的列 {},可选: {}", name, names.join(","))));
+            }
+        }
+    }
+    if columns.is_empty() {
+        return Err(AspenError::Usage("--columns 至少指定一列!".to_string()));
+    }
+
+    Ok(columns)
+}
+
+fn column_value(config: &ServerConfig, index: usize, column: &str, show_secrets: bool) -> Value {
+    match column {
+        "id" => Value::from(index + 1),
+        "title" => Value::from(config.title.clone()),
+        "host" => Value::from(config.host.clone()),
+        "port" => Value::from(config.port),
+        "category" => Value::from(config.category.clone()),
+        "username" => Value::from(config.username.clone()),
+        // 密码默认以占位符代替,未设置密码时为空
+        "password" if show_secrets || config.password.is_empty() => Value::from(config.password.clone()),
+        "password" => Value::from(MASKED),
+        "remark" => Value::from(config.remark.clone()),
+        "source" => Value::from(display_path(&config.source)),
+        "aliases" => Value::from(config.aliases.clone()),
+        "tags" => Value::from(config.tags.clone()),
+        "labels" => Value::from(Map::from_iter(config.labels.iter().map(|(key, value)| (key.clone(), Value::from(value.clone()))))),
+        "proxy_jump" => Value::from(config.proxy_jump.clone()),
+        "identity_file" => Value::from(config.identity_file.clone()),
+        "use_agent" => Value::from(config.use_agent),
+        "danger" => Value::from(config.danger),
+        _ => Value::Null,
+    }
+}
+
+// 文本格式中列表以逗号连接,空值为空字符串
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(value_text).collect::<Vec<String>>().join(","),
+        Value::Object(entries) => entries.iter().map(|(key, value)| format!("{}={}", key, value_text(value))).collect::<Vec<String>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+fn to_json_value(columns: &[&str], rows: &[Vec<Value>]) -> Value {
+    let objects: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let object: Map<String, Value> = columns.iter().map(|column| column.to_string()).zip(row.iter().cloned()).collect();
+            Value::Object(object)
+        })
+        .collect();
+
+    Value::Array(objects)
+}
+
+// 第一行为列名; tsv 中的制表符与换行替换为空格
+fn to_delimited(columns: &[&str], rows: &[Vec<Value>], separator: &str) -> String {
+    let field = |text: &str| -> String {
+        if separator == "," {
+            csv_field(text)
+        } else {
+            text.replace(['\t', '\r', '\n'], " ")
+        }
+    };
+
+    let mut lines: Vec<String> = vec![columns.join(separator)];
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|value| field(&value_text(value))).collect();
+        lines.push(fields.join(separator));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn to_table(columns: &[&str], rows: &[Vec<Value>], table_format: format::TableFormat, with_titles: bool) -> String {
+    let mut table = Table::new();
+    table.set_format(table_format);
+
+    if with_titles {
+        let titles = columns
+            .iter()
+            .map(|column| COLUMNS.iter().find(|(name, _)| name == column).map(|(_, title)| *title).unwrap_or(column))
+            .map(Cell::new)
+            .collect();
+        table.set_titles(Row::new(titles));
+    }
+    for row in rows {
+        table.add_row(Row::new(row.iter().map(|value| Cell::new(&value_text(value))).collect()));
+    }
+
+    table.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(title: &str, host: &str, category: &str) -> ServerConfig {
+        serde_json::from_value(serde_json::json!({
+            "title": title,
+            "category": category,
+            "host": host,
+            "port": 22,
+            "username": "root",
+            "remark": "",
+        }))
+        .unwrap()
+    }
+
+    fn servers() -> Vec<ServerConfig> {
+        vec![
+            server("web2", "10.0.0.12", "CentOS"),
+            server("Db1", "10.0.1.20", "Ubuntu"),
+            server("web1", "10.0.0.11", "centos"),
+            server("Cache", "10.0.2.30", "CentOS"),
+        ]
+    }
+
+    fn titles(servers: &[(usize, &ServerConfig)]) -> Vec<String> {
+        servers.iter().map(|(_, config)| config.title.clone()).collect()
+    }
+
+    #[test]
+    fn sorts_by_field_case_insensitively_and_stably() {
+        let configs = servers();
+        let mut list: Vec<(usize, &ServerConfig)> = configs.iter().enumerate().collect();
+
+        sort_servers(&mut list, "title");
+        assert_eq!(titles(&list), vec!["Cache", "Db1", "web1", "web2"]);
+        // 排序后保留原有序号
+        assert_eq!(list[0].0, 3);
+
+        sort_servers(&mut list, "host");
+        assert_eq!(titles(&list), vec!["web1", "web2", "Db1", "Cache"]);
+
+        // 分类相同(忽略大小写)时保持排序前的顺序
+        sort_servers(&mut list, "category");
+        assert_eq!(titles(&list), vec!["web1", "web2", "Cache", "Db1"]);
+    }
+
+    #[test]
+    fn sorts_by_last_used_with_unused_servers_last() {
+        let configs = servers();
+        let mut list: Vec<(usize, &ServerConfig)> = configs.iter().enumerate().collect();
+        let history = BTreeMap::from([("web1".to_string(), 200), ("Cache".to_string(), 300), ("Gone".to_string(), 900)]);
+
+        sort_by_last_used(&mut list, &history);
+        assert_eq!(titles(&list), vec!["Cache", "web1", "web2", "Db1"]);
+    }
+
+    #[test]
+    fn groups_by_category_in_first_seen_order() {
+        let configs = servers();
+        let list: Vec<(usize, &ServerConfig)> = configs.iter().enumerate().collect();
+
+        let groups = group_servers(&list, true);
+        let summary: Vec<(Option<&str>, Vec<usize>)> = groups
+            .iter()
+            .map(|(category, members)| (*category, members.iter().map(|(index, _)| *index).collect()))
+            .collect();
+        // 分类名区分大小写,与表格中显示的一致
+        assert_eq!(summary, vec![(Some("CentOS"), vec![0, 3]), (Some("Ubuntu"), vec![1]), (Some("centos"), vec![2])]);
+
+        let ungrouped = group_servers(&list, false);
+        assert_eq!(ungrouped.len(), 1);
+        assert_eq!(ungrouped[0].0, None);
+        assert_eq!(ungrouped[0].1.len(), 4);
+    }
+}
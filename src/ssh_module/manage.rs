@@ -0,0 +1,368 @@
+use std::{
+    collections::BTreeMap,
+    io::{stdin, stdout, Write},
+    path::{Path, PathBuf},
+};
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::config::{
+    find_server_index, get_config, get_servers_path, read_server_config, write_server_config, ServerConfig,
+};
+use crate::ssh_module::selector::Selector;
+use crate::ssh_module::target::resolve_target;
+use crate::vault_module::store::{prompt_secret, VAULT_PREFIX};
+
+// add、edit 共用的字段参数
+const FIELD_ARGS: [&str; 15] = [
+    "title", "category", "host", "port", "username", "password", "remark",
+    "identity-file", "passphrase", "use-agent", "aliases", "tags", "labels", "proxy-jump", "danger",
+];
+
+/**
+ * 新增服务器(未指定字段参数时进入交互向导)
+ */
+pub fn impl_add_server_action(matches: &ArgMatches) -> Result<()> {
+    let path = get_servers_path()?;
+    let mut configs = load_servers(&path)?;
+
+    let config = if matches.get_flag("interactive") || !has_field_args(matches) {
+        server_wizard(None)?
+    } else {
+        let mut config = empty_server();
+        apply_field_args(&mut config, matches)?;
+        config
+    };
+
+    let title = config.title.clone();
+    configs.push(config);
+    write_server_config(&path, &configs)?;
+
+    println!("\n[Aspen Success] ==> 已新增服务器【 {} 】到 {}\n", title.green(), path.display());
+    Ok(())
+}
+
+/**
+ * 编辑服务器(未指定字段参数时进入交互向导,回车保持原值)
+ */
+pub fn impl_edit_server_action(matches: &ArgMatches) -> Result<()> {
+    let filter = Selector::from_tag_label_args(matches)?;
+    let (path, mut configs, index) = locate_server(matches.get_one::<String>("target").unwrap(), &filter)?;
+
+    if has_field_args(matches) {
+        apply_field_args(&mut configs[index], matches)?;
+    } else {
+        configs[index] = server_wizard(Some(&configs[index]))?;
+    }
+
+    write_server_config(&path, &configs)?;
+
+    println!("\n[Aspen Success] ==> 已更新服务器【 {} 】\n", configs[index].title.green());
+    Ok(())
+}
+
+/**
+ * 删除服务器
+ */
+pub fn impl_rm_server_action(matches: &ArgMatches) -> Result<()> {
+    let target = matches.get_one::<String>("target").unwrap();
+    let (path, mut configs, index) = locate_server(target, &Selector::from_filter_args(matches)?)?;
+    let title = configs[index].title.clone();
+
+    // 按关键字匹配到的服务器即使指定了 --yes 也需确认,避免误删
+    if !matches.get_flag("yes") || find_server_index(&get_config()?, target).is_none() {
+        let answer = prompt_line(&format!("确认删除服务器【 {} 】? 输入 {} 确认", title.green(), "yes".green()), None)?;
+        if !answer.eq_ignore_ascii_case("yes") {
+            return Err(AspenError::Cancelled);
+        }
+    }
+
+    configs.remove(index);
+    write_server_config(&path, &configs)?;
+
+    println!("\n[Aspen Success] ==> 已删除服务器【 {} 】\n", title.green());
+    Ok(())
+}
+
+/**
+ * 复制服务器,副本插入在原服务器之后
+ */
+pub fn impl_cp_server_action(matches: &ArgMatches) -> Result<()> {
+    let (path, mut configs, index) = locate_server(matches.get_one::<String>("target").unwrap(), &Selector::from_filter_args(matches)?)?;
+    let new_title = matches.get_one::<String>("new-title").unwrap();
+
+    let config = copy_server(&configs[index], new_title);
+    configs.insert(index + 1, config);
+    write_server_config(&path, &configs)?;
+
+    println!("\n[Aspen Success] ==> 已复制为【 {} 】\n", new_title.green());
+    Ok(())
+}
+
+// 副本不能沿用原服务器的别名(标题、别名需唯一),端口转发的本地端口也会冲突,一并清空
+fn copy_server(source: &ServerConfig, new_title: &str) -> ServerConfig {
+    let mut config = source.clone();
+    config.title = new_title.to_string();
+    config.aliases.clear();
+    config.forwards.clear();
+    config.remote_forwards.clear();
+
+    config
+}
+
+/**
+ * 读取服务器配置,文件不存在时视为空列表,便于首次 add、import
+ */
+pub fn load_servers(path: &Path) -> Result<Vec<ServerConfig>> {
+    if path.exists() {
+        read_server_config(&path.to_path_buf())
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/**
+ * 按 序号、标题、别名 或 关键字 在合并后的列表中查找满足筛选条件的服务器(同 resolve_target)
+ * 返回其所在的配置文件、该文件中的服务器列表及下标
+ */
+pub fn locate_server(target: &str, filter: &Selector) -> Result<(PathBuf, Vec<ServerConfig>, usize)> {
+    let merged = get_config()?;
+    let source = &resolve_target(&merged, target, filter)?;
+
+    let configs = load_servers(&source.source)?;
+    let index = configs
+        .iter()
+        .position(|config| config.title == source.title)
+        .ok_or_else(|| AspenError::Config(format!("配置文件 {} 中未找到服务器【 {} 】,请重试!", source.source.display(), source.title)))?;
+
+    Ok((source.source.clone(), configs, index))
+}
+
+/**
+ * 字段均为空(端口 22)的服务器配置
+ */
+pub fn empty_server() -> ServerConfig {
+    ServerConfig {
+        title: String::new(),
+        category: String::new(),
+        host: String::new(),
+        port: 22,
+        username: String::new(),
+        password: String::new(),
+        remark: String::new(),
+        identity_file: None,
+        passphrase: None,
+        use_agent: false,
+        auth_order: Vec::new(),
+        aliases: Vec::new(),
+        tags: Vec::new(),
+        labels: BTreeMap::new(),
+        danger: false,
+        proxy_jump: None,
+        host_key_fingerprint: None,
+        forwards: Vec::new(),
+        remote_forwards: Vec::new(),
+        extra: BTreeMap::new(),
+        source: PathBuf::new(),
+    }
+}
+
+fn has_field_args(matches: &ArgMatches) -> bool {
+    FIELD_ARGS.iter().any(|name| matches.value_source(name).is_some())
+}
+
+// 将命令行参数中出现的字段写入配置
+fn apply_field_args(config: &mut ServerConfig, matches: &ArgMatches) -> Result<()> {
+    let value = |name: &str| matches.get_one::<String>(name).cloned();
+
+    if let Some(title) = value("title") {
+        config.title = title;
+    }
+    if let Some(category) = value("category") {
+        config.category = category;
+    }
+    if let Some(host) = value("host") {
+        config.host = host;
+    }
+    if let Some(port) = value("port") {
+        config.port = parse_port(&port)?;
+    }
+    if let Some(username) = value("username") {
+        config.username = username;
+    }
+    if let Some(password) = value("password") {
+        warn_secret_arg("--password", &password);
+        config.password = password;
+    }
+    if let Some(remark) = value("remark") {
+        config.remark = remark;
+    }
+    if let Some(identity_file) = value("identity-file") {
+        config.identity_file = Some(identity_file).filter(|s| !s.is_empty());
+    }
+    if let Some(passphrase) = value("passphrase") {
+        warn_secret_arg("--passphrase", &passphrase);
+        config.passphrase = Some(passphrase).filter(|s| !s.is_empty());
+    }
+    if let Some(use_agent) = matches.get_one::<bool>("use-agent") {
+        config.use_agent = *use_agent;
+    }
+    if let Some(aliases) = value("aliases") {
+        config.aliases = split_list(&aliases);
+    }
+    if let Some(tags) = value("tags") {
+        config.tags = split_list(&tags);
+    }
+    if let Some(labels) = value("labels") {
+        config.labels = parse_labels(&labels)?;
+    }
+    if let Some(proxy_jump) = value("proxy-jump") {
+        config.proxy_jump = Some(proxy_jump).filter(|s| !s.is_empty());
+    }
+    if let Some(danger) = matches.get_one::<bool>("danger") {
+        config.danger = *danger;
+    }
+
+    Ok(())
+}
+
+// 密码写在命令行中会出现在 ps 与 shell 历史中,保险箱引用除外
+fn warn_secret_arg(name: &str, value: &str) {
+    if value.is_empty() || value.starts_with(VAULT_PREFIX) {
+        return;
+    }
+
+    eprintln!(
+        "\n[Aspen Warning] => {}",
+        format!(
+            "{} 的值会被 ps 与 shell 历史记录,建议使用交互向导,或先 vault set 后填写 {}<key> 引用",
+            name, VAULT_PREFIX
+        )
+        .yellow()
+    );
+}
+
+// 交互向导,编辑时以原配置作为默认值
+fn server_wizard(current: Option<&ServerConfig>) -> Result<ServerConfig> {
+    let mut config = current.cloned().unwrap_or_else(empty_server);
+    let port = config.port.to_string();
+
+    println!("\n[Aspen Waiting] ==> {}\n", "请按提示输入服务器信息(回车使用括号中的默认值)".green());
+
+    config.title = prompt_line("标题", Some(&config.title))?;
+    config.category = prompt_line("分类", Some(&config.category))?;
+    config.host = prompt_line("主机地址", Some(&config.host))?;
+    config.port = parse_port(&prompt_line("端口", Some(&port))?)?;
+    config.username = prompt_line("用户名", Some(&config.username))?;
+
+    let password = prompt_secret(if current.is_some() { "密码(回车保持不变): " } else { "密码(可留空): " })?;
+    if !password.is_empty() {
+        config.password = password;
+    }
+
+    let identity_file = prompt_line("私钥文件(可留空)", config.identity_file.as_deref())?;
+    config.identity_file = Some(identity_file).filter(|s| !s.is_empty());
+    if config.identity_file.is_some() {
+        let passphrase = prompt_secret("私钥密码(回车保持不变): ")?;
+        if !passphrase.is_empty() {
+            config.passphrase = Some(passphrase);
+        }
+    }
+
+    let use_agent = prompt_line("使用 ssh-agent (yes/no)", Some(if config.use_agent { "yes" } else { "no" }))?;
+    config.use_agent = use_agent.eq_ignore_ascii_case("yes") || use_agent.eq_ignore_ascii_case("y");
+    config.aliases = split_list(&prompt_line("别名(逗号分隔,可留空)", Some(&config.aliases.join(",")))?);
+    config.tags = split_list(&prompt_line("标签(逗号分隔,可留空)", Some(&config.tags.join(",")))?);
+    config.labels = parse_labels(&prompt_line("键值标签(如 env=prod,team=payments,可留空)", Some(&config.labels_text()))?)?;
+    let proxy_jump = prompt_line("跳板机(标题或别名,可留空)", config.proxy_jump.as_deref())?;
+    config.proxy_jump = Some(proxy_jump).filter(|s| !s.is_empty());
+    let danger = prompt_line("敏感服务器,登录前需确认 (yes/no)", Some(if config.danger { "yes" } else { "no" }))?;
+    config.danger = danger.eq_ignore_ascii_case("yes") || danger.eq_ignore_ascii_case("y");
+    config.remark = prompt_line("备注", Some(&config.remark))?;
+
+    Ok(config)
+}
+
+// 逗号分隔的列表
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+}
+
+// 逗号分隔的 key=value 列表
+fn parse_labels(value: &str) -> Result<BTreeMap<String, String>> {
+    let mut labels: BTreeMap<String, String> = BTreeMap::new();
+    for item in split_list(value) {
+        match item.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                labels.insert(key.trim().to_string(), value.trim().to_string());
+            }
+            _ => return Err(AspenError::Usage(format!("键值标签 {} 应为 key=value 格式!", item))),
+        }
+    }
+
+    Ok(labels)
+}
+
+/**
+ * 读取一行输入,直接回车时返回默认值
+ */
+pub fn prompt_line(label: &str, default: Option<&str>) -> Result<String> {
+    match default {
+        Some(value) if !value.is_empty() => print!("{} [{}]: ", label, value),
+        _ => print!("{}: ", label),
+    }
+    stdout().flush().unwrap();
+
+    let mut input = String::new();
+    if stdin().read_line(&mut input).is_err() {
+        return Err(AspenError::Io("读取输入失败!".to_string()));
+    }
+
+    let input = input.trim();
+    if input.is_empty() {
+        Ok(default.unwrap_or_default().to_string())
+    } else {
+        Ok(input.to_string())
+    }
+}
+
+fn parse_port(value: &str) -> Result<u32> {
+    match value.trim().parse::<u32>() {
+        Ok(port) if (1..=65535).contains(&port) => Ok(port),
+        _ => Err(AspenError::Usage("端口必须是 1-65535 之间的整数!".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh_module::config::{ForwardConfig, RemoteForwardConfig};
+
+    #[test]
+    fn copy_drops_aliases_and_forwards() {
+        let mut source = empty_server();
+        source.title = "Web1".to_string();
+        source.host = "10.0.0.1".to_string();
+        source.username = "root".to_string();
+        source.aliases = vec!["w1".to_string()];
+        source.tags = vec!["web".to_string()];
+        source.forwards = vec![ForwardConfig {
+            name: "mysql".to_string(),
+            local_bind: "13306".to_string(),
+            remote_host: "127.0.0.1".to_string(),
+            remote_port: 3306,
+        }];
+        source.remote_forwards = vec![RemoteForwardConfig::parse("8080:127.0.0.1:3000").unwrap()];
+
+        let copy = copy_server(&source, "Web2");
+
+        assert_eq!(copy.title, "Web2");
+        assert_eq!(copy.host, source.host);
+        assert_eq!(copy.tags, source.tags);
+        assert!(copy.aliases.is_empty());
+        assert!(copy.forwards.is_empty());
+        assert!(copy.remote_forwards.is_empty());
+        assert_eq!(source.aliases, vec!["w1".to_string()]);
+    }
+}
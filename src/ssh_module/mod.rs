@@ -1,2 +1,18 @@
 pub mod config;
-pub mod command;
\ No newline at end of file
+pub mod command;
+pub mod session;
+pub mod shell;
+pub mod known_hosts;
+pub mod manage;
+pub mod target;
+pub mod picker;
+pub mod exec;
+pub mod selector;
+pub mod ssh_config;
+pub mod import;
+pub mod export;
+pub mod listing;
+pub mod history;
+pub mod guard;
+#[cfg(any(target_os = "macos", test))]
+pub mod script;
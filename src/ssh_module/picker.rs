@@ -0,0 +1,320 @@
+use std::io::{self, stdout, Write};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::ssh_module::config::ServerConfig;
+
+// 列表中的一行: 分类标题或服务器
+enum Row {
+    Header(String, usize),
+    Item(usize),
+}
+
+// 全屏界面守卫,离开作用域时恢复终端
+struct ScreenGuard;
+
+impl ScreenGuard {
+    fn enter() -> io::Result<ScreenGuard> {
+        enable_raw_mode()?;
+        if let Err(e) = queue!(stdout(), EnterAlternateScreen, Hide).and_then(|_| stdout().flush()) {
+            let _ = disable_raw_mode();
+            return Err(e);
+        }
+
+        Ok(ScreenGuard)
+    }
+}
+
+impl Drop for ScreenGuard {
+    fn drop(&mut self) {
+        let _ = queue!(stdout(), Show, LeaveAlternateScreen);
+        let _ = stdout().flush();
+        let _ = disable_raw_mode();
+    }
+}
+
+struct Picker<'a> {
+    lines: &'a [ServerConfig],
+    hint: Option<&'a str>,
+    query: String,
+    // 过滤并按分类分组后的服务器下标
+    items: Vec<usize>,
+    selected: usize,
+    scroll: usize,
+}
+
+/**
+ * 全屏选择服务器: 输入即过滤,方向键移动,回车确认,Esc 取消
+ * 返回 None 表示用户取消; Err 表示终端不支持全屏界面
+ */
+pub fn pick_server_tui(lines: &[ServerConfig], hint: Option<&str>) -> io::Result<Option<usize>> {
+    let _guard = ScreenGuard::enter()?;
+
+    let mut picker = Picker {
+        lines,
+        hint,
+        query: String::new(),
+        items: Vec::new(),
+        selected: 0,
+        scroll: 0,
+    };
+    picker.filter();
+
+    loop {
+        picker.render()?;
+
+        match read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => {
+                if let Some(result) = picker.handle_key(key) {
+                    return Ok(result);
+                }
+            }
+            Event::Paste(text) => {
+                picker.query.push_str(text.trim());
+                picker.filter();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a> Picker<'a> {
+    // 处理按键,返回 Some 表示结束选择
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Option<usize>> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => return Some(None),
+            KeyCode::Char('c') | KeyCode::Char('d') if ctrl => return Some(None),
+            KeyCode::Enter => {
+                if let Some(index) = self.items.get(self.selected) {
+                    return Some(Some(*index));
+                }
+            }
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Char('p') | KeyCode::Char('k') if ctrl => self.move_by(-1),
+            KeyCode::Down | KeyCode::Tab => self.move_by(1),
+            KeyCode::Char('n') | KeyCode::Char('j') if ctrl => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-10),
+            KeyCode::PageDown => self.move_by(10),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = self.items.len().saturating_sub(1),
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.filter();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.filter();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.filter();
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.items.is_empty() {
+            return;
+        }
+
+        let max = self.items.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).clamp(0, max) as usize;
+    }
+
+    // 按关键字过滤(标题、主机、分类、别名、备注),并按分类首次出现的顺序分组
+    fn filter(&mut self) {
+        let query = self.query.to_lowercase();
+        let mut categories: Vec<&str> = Vec::new();
+        let mut matched: Vec<usize> = Vec::new();
+
+        for (index, line) in self.lines.iter().enumerate() {
+            let fields = [&line.title, &line.host, &line.category, &line.remark];
+            let hit = query.is_empty()
+                || fields.iter().any(|field| field.to_lowercase().contains(&query))
+                || line.aliases.iter().any(|alias| alias.to_lowercase().contains(&query));
+
+            if hit {
+                if !categories.contains(&line.category.as_str()) {
+                    categories.push(&line.category);
+                }
+                matched.push(index);
+            }
+        }
+
+        self.items = categories
+            .iter()
+            .flat_map(|category| matched.iter().copied().filter(|index| self.lines[*index].category == *category))
+            .collect();
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows: Vec<Row> = Vec::new();
+        let mut current: Option<&str> = None;
+
+        for index in &self.items {
+            let category = self.lines[*index].category.as_str();
+            if current != Some(category) {
+                let count = self.items.iter().filter(|i| self.lines[**i].category == category).count();
+                rows.push(Row::Header(category.to_string(), count));
+                current = Some(category);
+            }
+            rows.push(Row::Item(*index));
+        }
+
+        rows
+    }
+
+    fn render(&mut self) -> io::Result<()> {
+        let mut out = stdout();
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        let (width, height) = (width as usize, height as usize);
+        let list_width = (width * 11 / 20).max(20).min(width);
+        let body_height = height.saturating_sub(4).max(1);
+
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+
+        // 顶部: 搜索框
+        queue!(
+            out,
+            SetForegroundColor(Color::Green),
+            Print("Aspen › "),
+            ResetColor,
+            Print(truncate(&self.query, width.saturating_sub(30))),
+            SetForegroundColor(Color::DarkGrey),
+            Print(format!("  {}/{}", self.items.len(), self.lines.len())),
+            ResetColor
+        )?;
+        if let Some(hint) = self.hint {
+            queue!(out, Print("  "), Print(truncate(hint, width.saturating_sub(40))))?;
+        }
+        queue!(out, MoveTo(0, 1), Print("─".repeat(width)))?;
+
+        // 左侧: 分组列表,保证选中行可见
+        let rows = self.rows();
+        let selected_row = rows
+            .iter()
+            .position(|row| matches!(row, Row::Item(index) if Some(index) == self.items.get(self.selected)))
+            .unwrap_or(0);
+        if selected_row < self.scroll {
+            self.scroll = selected_row.saturating_sub(1);
+        } else if selected_row >= self.scroll + body_height {
+            self.scroll = selected_row + 1 - body_height;
+        }
+
+        for (offset, row) in rows.iter().skip(self.scroll).take(body_height).enumerate() {
+            queue!(out, MoveTo(0, (offset + 2) as u16))?;
+            match row {
+                Row::Header(category, count) => {
+                    let name = if category.is_empty() { "未分类" } else { category };
+                    queue!(
+                        out,
+                        SetForegroundColor(Color::Yellow),
+                        Print(truncate(&format!("▸ {} ({})", name, count), list_width)),
+                        ResetColor
+                    )?;
+                }
+                Row::Item(index) => {
+                    let line = &self.lines[*index];
+                    let text = pad(&format!("   {}  {}", line.title, line.host), list_width.saturating_sub(1));
+                    // 敏感服务器以红色显示
+                    if line.danger {
+                        queue!(out, SetForegroundColor(Color::Red))?;
+                    }
+                    if Some(index) == self.items.get(self.selected) {
+                        queue!(out, SetAttribute(Attribute::Reverse), Print(text), SetAttribute(Attribute::Reset))?;
+                    } else {
+                        queue!(out, Print(text))?;
+                    }
+                    queue!(out, ResetColor)?;
+                }
+            }
+        }
+
+        // 右侧: 预览选中服务器
+        if let Some(index) = self.items.get(self.selected) {
+            let line = &self.lines[*index];
+            let preview_width = width.saturating_sub(list_width + 3);
+            let fields = [
+                ("Title", line.title.clone()),
+                ("Host", line.host.clone()),
+                ("Port", line.port.to_string()),
+                ("Username", line.username.clone()),
+                ("Server Type", line.category.clone()),
+                ("Aliases", line.aliases.join(", ")),
+                ("Labels", line.labels_text()),
+                ("Auth", line.auth_methods().iter().map(|m| m.name()).collect::<Vec<&str>>().join(" > ")),
+                ("Remark", line.remark.clone()),
+            ];
+
+            for (offset, (name, value)) in fields.iter().enumerate().take(body_height) {
+                queue!(
+                    out,
+                    MoveTo((list_width + 1) as u16, (offset + 2) as u16),
+                    Print("│ "),
+                    SetForegroundColor(Color::Green),
+                    Print(format!("{:<12}", name)),
+                    ResetColor,
+                    Print(truncate(value, preview_width.saturating_sub(12)))
+                )?;
+            }
+        } else {
+            queue!(out, MoveTo(2, 2), SetForegroundColor(Color::DarkGrey), Print("没有匹配的服务器"), ResetColor)?;
+        }
+
+        queue!(
+            out,
+            MoveTo(0, (height.saturating_sub(1)) as u16),
+            SetForegroundColor(Color::DarkGrey),
+            Print(truncate("输入以过滤  ↑/↓ 选择  Enter 登录  Ctrl-U 清空  Esc 退出", width)),
+            ResetColor
+        )?;
+
+        out.flush()
+    }
+}
+
+/**
+ * 终端显示宽度(中日韩等宽字符按 2 计算)
+ */
+pub fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x1F300..=0x1F64F | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    let mut result = String::new();
+    let mut used = 0;
+
+    for c in text.chars().filter(|c| !c.is_control()) {
+        let w = char_width(c);
+        if used + w > width {
+            break;
+        }
+        used += w;
+        result.push(c);
+    }
+
+    result
+}
+
+fn pad(text: &str, width: usize) -> String {
+    let text = truncate(text, width);
+    let used: usize = text.chars().map(char_width).sum();
+
+    format!("{}{}", text, " ".repeat(width.saturating_sub(used)))
+}
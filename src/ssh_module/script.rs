@@ -0,0 +1,203 @@
+use std::process::Command;
+
+use crate::ssh_module::config::{AuthMethod, ServerConfig};
+
+// 传递凭据的环境变量,脚本读取后立即清除
+pub const PASSWORD_ENV: &str = "ASPEN_PASSWORD";
+pub const PASSPHRASE_ENV: &str = "ASPEN_PASSPHRASE";
+
+// 登录入口脚本,参数原样透传给 expect,不经过 eval
+pub const CONTROLLER_SCRIPT: &str = r#"#!/bin/bash
+
+current_dir=$(dirname "$(realpath "$0")")
+
+exec "$(which expect)" "$current_dir/script.ex" "$@"
+"#;
+
+// expect 登录脚本,参数: 名称 IP 端口 用户名 [ssh参数...]
+pub const EXPECT_SCRIPT: &str = r#"#!/usr/bin/expect
+
+set SERVER_NAME [lindex $argv 0]
+set IP [lindex $argv 1]
+set PORT [lindex $argv 2]
+set USER_NAME [lindex $argv 3]
+set SSH_OPTIONS [lrange $argv 4 end]
+
+set PASSWORD ""
+if {[info exists env(ASPEN_PASSWORD)]} {
+    set PASSWORD $env(ASPEN_PASSWORD)
+    unset env(ASPEN_PASSWORD)
+}
+
+set PASSPHRASE ""
+if {[info exists env(ASPEN_PASSPHRASE)]} {
+    set PASSPHRASE $env(ASPEN_PASSPHRASE)
+    unset env(ASPEN_PASSPHRASE)
+}
+
+spawn ssh -p $PORT {*}$SSH_OPTIONS $USER_NAME@$IP
+
+expect {
+    -timeout 300
+    "*passphrase*" { send -- "$PASSPHRASE\r"; exp_continue; }
+    "*assword" { send -- "$PASSWORD\r"; exp_continue; }
+    "Last*" {
+        puts "\nLogin Successful!!!\n";
+    }
+    timeout { puts "Expect was timeout."; return }
+}
+
+interact
+"#;
+
+/**
+ * 构建执行登录脚本的命令,密码与私钥密码只通过环境变量传递
+ */
+pub fn login_command(config: &ServerConfig, controller_path: &str) -> Command {
+    let mut command = Command::new("sh");
+
+    command
+        .arg("-e")
+        .arg(controller_path)
+        .arg(&config.title)
+        .arg(&config.host)
+        .arg(config.port.to_string())
+        .arg(&config.username)
+        .args(ssh_auth_options(config))
+        .env(PASSWORD_ENV, &config.password)
+        .env(PASSPHRASE_ENV, config.passphrase.clone().unwrap_or_default());
+
+    command
+}
+
+// 按服务器配置的认证顺序生成 ssh 命令参数
+fn ssh_auth_options(config: &ServerConfig) -> Vec<String> {
+    let methods = config.auth_methods();
+    // 主机公钥已在登录前校验并写入 known_hosts,不再接受未知主机
+    let mut options: Vec<String> = vec!["-o".to_string(), "StrictHostKeyChecking=yes".to_string()];
+
+    if let Some(identity) = config.identity_path() {
+        options.push("-i".to_string());
+        options.push(identity.to_string_lossy().to_string());
+    }
+
+    if !methods.contains(&AuthMethod::Agent) {
+        options.push("-o".to_string());
+        options.push("IdentityAgent=none".to_string());
+    }
+
+    // agent 与私钥在 ssh 中同属 publickey 认证
+    let mut preferred: Vec<&str> = Vec::new();
+    for method in methods {
+        let names: &[&str] = match method {
+            AuthMethod::Agent | AuthMethod::Key => &["publickey"],
+            AuthMethod::Password => &["keyboard-interactive", "password"],
+        };
+        for name in names {
+            if !preferred.contains(name) {
+                preferred.push(name);
+            }
+        }
+    }
+
+    if !preferred.is_empty() {
+        options.push("-o".to_string());
+        options.push(format!("PreferredAuthentications={}", preferred.join(",")));
+    }
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, fs, path::PathBuf, process};
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    const HOSTILE_PASSWORDS: &[&str] = &[
+        "pa$$word",
+        "$(touch /tmp/aspen-pwned)",
+        "`id`",
+        "with spaces  and\ttabs",
+        "quote'single\"double",
+        "semi;colon|pipe&amp",
+        "-rf",
+        "[exec id]",
+        "{*}$argv",
+        "back\\slash\\r",
+    ];
+
+    fn server(password: &str) -> ServerConfig {
+        serde_json::from_value(serde_json::json!({
+            "title": "Title With Space",
+            "category": "",
+            "host": "127.0.0.1",
+            "port": 2222,
+            "username": "root",
+            "password": password,
+            "passphrase": password,
+            "remark": "",
+        }))
+        .unwrap()
+    }
+
+    // 在临时目录中放置 controller.sh 与伪造的 expect,用于观察脚本实际收到的参数
+    #[cfg(unix)]
+    fn stub_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aspen-script-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("controller.sh"), CONTROLLER_SCRIPT).unwrap();
+
+        let expect = dir.join("expect");
+        fs::write(&expect, "#!/bin/sh\nshift\nfor arg in \"$@\"; do printf 'ARG:%s\\n' \"$arg\"; done\nprintf 'ENV:%s\\n' \"$ASPEN_PASSWORD\"\n").unwrap();
+        fs::set_permissions(&expect, fs::Permissions::from_mode(0o755)).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn password_is_never_passed_as_argument() {
+        for password in HOSTILE_PASSWORDS {
+            let command = login_command(&server(password), "/tmp/controller.sh");
+
+            assert!(command.get_args().all(|arg| !arg.to_string_lossy().contains(password)));
+            let envs: Vec<_> = command.get_envs().collect();
+            assert!(envs.contains(&(OsStr::new(PASSWORD_ENV), Some(OsStr::new(password)))));
+            assert!(envs.contains(&(OsStr::new(PASSPHRASE_ENV), Some(OsStr::new(password)))));
+        }
+    }
+
+    #[test]
+    fn expect_script_reads_credentials_from_environment() {
+        assert!(EXPECT_SCRIPT.contains(&format!("unset env({})", PASSWORD_ENV)));
+        assert!(EXPECT_SCRIPT.contains(&format!("unset env({})", PASSPHRASE_ENV)));
+        assert!(EXPECT_SCRIPT.contains("send -- \"$PASSWORD\\r\""));
+        assert!(!CONTROLLER_SCRIPT.contains("eval"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn controller_forwards_hostile_credentials_verbatim() {
+        let dir = stub_dir("hostile");
+        let controller = dir.join("controller.sh");
+        let path = format!("{}:{}", dir.display(), std::env::var("PATH").unwrap_or_default());
+
+        for password in HOSTILE_PASSWORDS {
+            let output = login_command(&server(password), controller.to_str().unwrap())
+                .env("PATH", &path)
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8(output.stdout).unwrap();
+
+            assert!(output.status.success(), "controller.sh 执行失败: {}", String::from_utf8_lossy(&output.stderr));
+            assert!(stdout.starts_with("ARG:Title With Space\nARG:127.0.0.1\nARG:2222\nARG:root\n"));
+            assert!(stdout.ends_with(&format!("ENV:{}\n", password)));
+            assert!(!stdout.contains(&format!("ARG:{}", password)));
+        }
+
+        assert!(!PathBuf::from("/tmp/aspen-pwned").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+use clap::ArgMatches;
+
+use crate::aspen_module::error::AspenError;
+use crate::ssh_module::config::ServerConfig;
+use crate::ssh_module::known_hosts::wildcard_match;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    // 未指定字段时匹配 标题、主机、分类、别名、标签、键值标签的值 中任意一个
+    Any,
+    Title,
+    Host,
+    Category,
+    Username,
+    Alias,
+    Tag,
+    // 其他键按键值标签匹配,如 env=prod
+    Label(String),
+}
+
+#[derive(Debug)]
+struct Term {
+    field: Field,
+    // 匹配其中任意一个即可,key in (a,b) 时有多个
+    patterns: Vec<String>,
+    negate: bool,
+}
+
+/**
+ * 服务器选择表达式,多个条件以逗号分隔,需同时满足
+ * 如: all、centos、category=CentOS、tag=web,host!=10.0.0.*、env=prod,team!=infra、env in (prod,staging)、team notin (infra)
+ */
+#[derive(Debug)]
+pub struct Selector {
+    terms: Vec<Term>,
+}
+
+impl Selector {
+    pub fn parse(expr: &str) -> Result<Selector, String> {
+        let mut terms: Vec<Term> = Vec::new();
+
+        for part in split_terms(expr)? {
+            // all、* 表示全部服务器
+            if part.eq_ignore_ascii_case("all") || part == "*" {
+                continue;
+            }
+
+            let (key, patterns, negate) = split_term(part)?;
+
+            let field = match key {
+                None => Field::Any,
+                Some(key) => match key.to_lowercase().as_str() {
+                    "title" => Field::Title,
+                    "host" => Field::Host,
+                    "category" | "type" => Field::Category,
+                    "user" | "username" => Field::Username,
+                    "alias" | "aliases" => Field::Alias,
+                    "tag" | "tags" => Field::Tag,
+                    "" => return Err(format!("选择条件 {} 缺少字段名", part)),
+                    _ => Field::Label(key.to_string()),
+                },
+            };
+
+            terms.push(Term { field, patterns, negate });
+        }
+
+        Ok(Selector { terms })
+    }
+
+    /**
+     * 由 --category、--host、--tag、--label 参数组成的筛选条件(需同时满足),未指定时匹配全部服务器
+     * --label 只写键时表示带有该键值标签
+     */
+    pub fn from_filter_args(matches: &ArgMatches) -> Result<Selector, AspenError> {
+        Selector::from_args(matches, &[("category", Field::Category), ("host", Field::Host), ("tag", Field::Tag)])
+    }
+
+    /**
+     * 只由 --tag、--label 组成的筛选条件,供 edit 命令使用(其 --category、--host 用于修改字段)
+     */
+    pub fn from_tag_label_args(matches: &ArgMatches) -> Result<Selector, AspenError> {
+        Selector::from_args(matches, &[("tag", Field::Tag)])
+    }
+
+    fn from_args(matches: &ArgMatches, fields: &[(&str, Field)]) -> Result<Selector, AspenError> {
+        let values = |name: &str| -> Vec<String> {
+            match matches.try_get_many::<String>(name) {
+                Ok(Some(values)) => values.cloned().collect(),
+                _ => Vec::new(),
+            }
+        };
+
+        let mut terms: Vec<Term> = Vec::new();
+        for (name, field) in fields {
+            for pattern in values(name) {
+                terms.push(Term { field: field.clone(), patterns: vec![pattern], negate: false });
+            }
+        }
+        for value in values("label") {
+            for part in split_terms(&value).map_err(AspenError::Usage)? {
+                let term = match split_term(part).map_err(AspenError::Usage)? {
+                    (Some(key), patterns, negate) => Term { field: Field::Label(key.to_string()), patterns, negate },
+                    (None, _, _) => Term { field: Field::Label(part.to_string()), patterns: vec!["*".to_string()], negate: false },
+                };
+                terms.push(term);
+            }
+        }
+
+        Ok(Selector { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, config: &ServerConfig) -> bool {
+        self.terms.iter().all(|term| term.matches(config) != term.negate)
+    }
+}
+
+impl Term {
+    fn matches(&self, config: &ServerConfig) -> bool {
+        self.patterns.iter().any(|pattern| self.matches_pattern(pattern, config))
+    }
+
+    fn matches_pattern(&self, pattern: &str, config: &ServerConfig) -> bool {
+        let text = |value: &str| text_matches(pattern, value);
+        let exact = |values: &[String]| values.iter().any(|value| exact_matches(pattern, value));
+
+        match &self.field {
+            Field::Any => {
+                text(&config.title) || text(&config.host) || text(&config.category)
+                    || exact(&config.aliases) || exact(&config.tags)
+                    || config.labels.values().any(|value| exact_matches(pattern, value))
+            }
+            Field::Title => text(&config.title),
+            Field::Host => text(&config.host),
+            Field::Category => text(&config.category),
+            Field::Username => text(&config.username),
+            Field::Alias => exact(&config.aliases),
+            Field::Tag => exact(&config.tags),
+            // 键忽略大小写,没有该键时不匹配(因此 key!=value 对没有该键的服务器成立)
+            Field::Label(key) => config
+                .labels
+                .iter()
+                .any(|(name, value)| name.eq_ignore_ascii_case(key) && exact_matches(pattern, value)),
+        }
+    }
+}
+
+// 按括号外的逗号拆分条件,括号内的逗号属于 in (a,b) 的值列表
+fn split_terms(expr: &str) -> Result<Vec<&str>, String> {
+    let mut parts: Vec<&str> = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, c) in expr.char_indices() {
+        match c {
+            '(' if depth == 0 => depth = 1,
+            '(' => return Err(format!("选择表达式 {} 中括号不能嵌套", expr)),
+            ')' if depth == 1 => depth = 0,
+            ')' => return Err(format!("选择表达式 {} 中括号不匹配", expr)),
+            ',' if depth == 0 => {
+                parts.push(&expr[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(format!("选择表达式 {} 中括号不匹配", expr));
+    }
+    parts.push(&expr[start..]);
+
+    Ok(parts.into_iter().map(str::trim).filter(|part| !part.is_empty()).collect())
+}
+
+// 拆分 键=值、键!=值、键 in (值,值)、键 notin (值,值),没有键时只有值
+fn split_term(part: &str) -> Result<(Option<&str>, Vec<String>, bool), String> {
+    if let Some((key, rest)) = part.split_once(char::is_whitespace) {
+        let rest = rest.trim_start();
+        let (operator, list) = rest.split_at(rest.find(|c: char| c == '(' || c.is_whitespace()).unwrap_or(rest.len()));
+        let negate = match operator.to_lowercase().as_str() {
+            "in" => Some(false),
+            "notin" => Some(true),
+            _ => None,
+        };
+
+        if let Some(negate) = negate {
+            let values = list
+                .trim()
+                .strip_prefix('(')
+                .and_then(|list| list.strip_suffix(')'))
+                .ok_or_else(|| format!("选择条件 {} 的值列表应写在括号中,如 env in (prod,staging)", part))?;
+            let patterns: Vec<String> =
+                values.split(',').map(str::trim).filter(|value| !value.is_empty()).map(str::to_string).collect();
+            if patterns.is_empty() {
+                return Err(format!("选择条件 {} 缺少匹配值", part));
+            }
+            return Ok((Some(key), patterns, negate));
+        }
+    }
+    if part.contains(['(', ')']) {
+        return Err(format!("选择条件 {} 格式错误,括号只能用于 in (...) 与 notin (...)", part));
+    }
+
+    let (key, pattern, negate) = match part.split_once("!=") {
+        Some((key, pattern)) => (Some(key.trim()), pattern.trim(), true),
+        None => match part.split_once('=') {
+            Some((key, pattern)) => (Some(key.trim()), pattern.trim(), false),
+            None => (None, part.trim(), false),
+        },
+    };
+    if pattern.is_empty() {
+        return Err(format!("选择条件 {} 缺少匹配值", part));
+    }
+
+    Ok((key, vec![pattern.to_string()], negate))
+}
+
+// 含通配符时整体匹配,否则为忽略大小写的包含匹配
+fn text_matches(pattern: &str, value: &str) -> bool {
+    if has_wildcard(pattern) {
+        wildcard_match(pattern, value)
+    } else {
+        value.to_lowercase().contains(&pattern.to_lowercase())
+    }
+}
+
+// 别名、标签需完整匹配(忽略大小写,支持通配符)
+fn exact_matches(pattern: &str, value: &str) -> bool {
+    if has_wildcard(pattern) {
+        wildcard_match(pattern, value)
+    } else {
+        value.eq_ignore_ascii_case(pattern)
+    }
+}
+
+fn has_wildcard(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/**
+ * 按选择表达式筛选服务器,表达式非法时返回参数错误
+ */
+pub fn select_servers(configs: &[ServerConfig], expr: &str) -> Result<Vec<ServerConfig>, AspenError> {
+    let selector = Selector::parse(expr).map_err(AspenError::Usage)?;
+
+    Ok(configs.iter().filter(|config| selector.matches(config)).cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(title: &str, labels: &[(&str, &str)]) -> ServerConfig {
+        let mut config: ServerConfig = serde_json::from_value(serde_json::json!({
+            "title": title,
+            "category": "CentOS",
+            "host": "10.0.0.1",
+            "port": 22,
+            "username": "root",
+            "remark": "",
+            "tags": ["web"],
+        }))
+        .unwrap();
+        config.labels = labels.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        config
+    }
+
+    fn select(expr: &str) -> Vec<String> {
+        let configs = vec![
+            server("Web1", &[("env", "prod"), ("team", "payments")]),
+            server("Web2", &[("env", "staging"), ("team", "infra")]),
+            server("Web3", &[("env", "dev")]),
+        ];
+        select_servers(&configs, expr).unwrap().into_iter().map(|config| config.title).collect()
+    }
+
+    #[test]
+    fn label_equals_and_not_equals() {
+        assert_eq!(select("env=prod"), vec!["Web1"]);
+        assert_eq!(select("ENV=Prod"), vec!["Web1"]);
+        assert_eq!(select("env=*g*"), vec!["Web2"]);
+        // 没有 team 标签的服务器也满足 team!=infra
+        assert_eq!(select("team!=infra"), vec!["Web1", "Web3"]);
+        assert_eq!(select("env=prod,team!=infra"), vec!["Web1"]);
+        assert_eq!(select("region=eu"), Vec::<String>::new());
+        // 未指定字段时也匹配键值标签的值
+        assert_eq!(select("staging"), vec!["Web2"]);
+    }
+
+    #[test]
+    fn label_in_and_notin() {
+        assert_eq!(select("env in (prod,staging)"), vec!["Web1", "Web2"]);
+        assert_eq!(select("env in ( prod , dev )"), vec!["Web1", "Web3"]);
+        assert_eq!(select("env notin (prod,staging)"), vec!["Web3"]);
+        assert_eq!(select("env in (prod,staging),team!=infra"), vec!["Web1"]);
+        assert_eq!(select("title IN (web1,web3),env!=dev"), vec!["Web1"]);
+    }
+
+    #[test]
+    fn commas_and_all() {
+        assert_eq!(select("all"), vec!["Web1", "Web2", "Web3"]);
+        assert_eq!(select("tag=web, ,env=dev,"), vec!["Web3"]);
+        assert_eq!(split_terms("a=1,b in (x,y),c").unwrap(), vec!["a=1", "b in (x,y)", "c"]);
+    }
+
+    #[test]
+    fn malformed_selectors_are_rejected() {
+        for expr in ["=prod", "env=", "env!=", "env in (", "env in ()", "env in prod", "env in (a,(b))", "env=(prod)", "env) in (a"] {
+            assert!(Selector::parse(expr).is_err(), "{} 应解析失败", expr);
+        }
+        assert!(matches!(select_servers(&[], "env="), Err(AspenError::Usage(_))));
+    }
+}
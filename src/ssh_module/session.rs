@@ -0,0 +1,210 @@
+use std::{net::TcpStream, thread, time::Duration};
+use ssh2::{ErrorCode, Session};
+
+use crate::aspen_module::error::AspenError;
+use crate::ssh_module::config::{find_server_index, get_config, AuthMethod, ServerConfig};
+use crate::ssh_module::history::record_usage;
+use crate::ssh_module::known_hosts::{check_trusted_host_key, verify_host_key};
+use crate::tunnel_module::pump::bridge_channel;
+use crate::vault_module::store::Vault;
+
+// libssh2 非阻塞模式下"稍后重试"的错误码
+const LIBSSH2_ERROR_EAGAIN: i32 = -37;
+
+/**
+ * 与服务器建立ssh2会话(TCP链接、协议协商、身份认证)
+ * 配置了 proxy_jump 时先逐级链接跳板机,会话建立后记录服务器的使用时间
+ */
+pub fn connect_session(config: &ServerConfig) -> Result<Session, AspenError> {
+    let sess = connect_through(config, &mut Vec::new())?;
+    record_usage(&config.title);
+
+    Ok(sess)
+}
+
+fn connect_through(config: &ServerConfig, chain: &mut Vec<String>) -> Result<Session, AspenError> {
+    let config = &config.with_resolved_secrets()?;
+
+    let sess = handshake_through(config, chain, None)?;
+    verify_host_key(&sess, config)?;
+    try_authenticate(&sess, config)?;
+
+    Ok(sess)
+}
+
+/**
+ * 与服务器建立TCP链接并完成传输层协议协商(尚未认证)
+ */
+pub fn handshake_session(config: &ServerConfig) -> Result<Session, AspenError> {
+    handshake_through(config, &mut Vec::new(), None)
+}
+
+/**
+ * 建立ssh2会话,主机公钥必须已被信任,不会交互确认,供批量执行等场景使用
+ * 传入的配置及跳板机链(见 resolve_jump_chain)需已解析保险库引用
+ */
+pub fn try_connect_session(config: &ServerConfig, jumps: &[ServerConfig]) -> Result<Session, AspenError> {
+    try_connect_through(config, &mut Vec::new(), jumps)
+}
+
+/**
+ * 按 proxy_jump 依次查找服务器的各级跳板机(由近及远)并解析保险库引用
+ * 供批量执行在主线程中预先解析,工作线程中不再询问主密码
+ */
+pub fn resolve_jump_chain(config: &ServerConfig, vault: &mut Option<Vault>) -> Result<Vec<ServerConfig>, AspenError> {
+    let mut chain = vec![config.title.clone()];
+    let mut jumps: Vec<ServerConfig> = Vec::new();
+    let mut next = config.proxy_jump.clone();
+
+    while let Some(name) = next {
+        let jump = find_jump(&name, &chain)?;
+        chain.push(jump.title.clone());
+        next = jump.proxy_jump.clone();
+        jumps.push(jump.resolve_secrets_with(vault)?);
+    }
+
+    Ok(jumps)
+}
+
+fn try_connect_through(config: &ServerConfig, chain: &mut Vec<String>, jumps: &[ServerConfig]) -> Result<Session, AspenError> {
+    let sess = handshake_through(config, chain, Some(jumps))?;
+    check_trusted_host_key(&sess, config)?;
+    try_authenticate(&sess, config)?;
+
+    Ok(sess)
+}
+
+// chain 记录已经过的服务器标题,用于发现 proxy_jump 循环引用
+// jumps 为 None 时按需查找跳板机并可交互确认,否则使用预先解析好的跳板机链
+fn handshake_through(config: &ServerConfig, chain: &mut Vec<String>, jumps: Option<&[ServerConfig]>) -> Result<Session, AspenError> {
+    let tcp = open_stream(config, chain, jumps)?;
+
+    let mut sess = Session::new().map_err(|_| AspenError::Network("与主机进行 Session 链接失败！".to_string()))?;
+    sess.set_tcp_stream(tcp);
+    sess.handshake()
+        .map_err(|_| AspenError::Network(format!("与【 {} 】进行传输层协议协商失败!", config.title)))?;
+
+    Ok(sess)
+}
+
+// 直连服务器,或经跳板机的 direct-tcpip 通道链接服务器
+fn open_stream(config: &ServerConfig, chain: &mut Vec<String>, jumps: Option<&[ServerConfig]>) -> Result<TcpStream, AspenError> {
+    let port = u16::try_from(config.port)
+        .ok()
+        .filter(|port| *port > 0)
+        .ok_or_else(|| AspenError::Config(format!("【 {} 】的端口 {} 超出范围(1-65535)!", config.title, config.port)))?;
+
+    let jump = match &config.proxy_jump {
+        Some(jump) => jump,
+        None => {
+            return TcpStream::connect((config.host.as_str(), port)).map_err(|_| {
+                AspenError::Network("链接超时,请检查您的网络是否通畅或者您的Host信息是否正确".to_string())
+            });
+        }
+    };
+
+    chain.push(config.title.clone());
+    let (jump_config, jump_sess) = match jumps {
+        None => {
+            let jump_config = find_jump(jump, chain)?;
+            let jump_sess = connect_through(&jump_config, chain)?;
+            (jump_config, jump_sess)
+        }
+        Some(jumps) => {
+            let (jump_config, rest) = jumps
+                .split_first()
+                .ok_or_else(|| AspenError::Config(format!("【 {} 】的跳板机 {} 尚未解析!", config.title, jump)))?;
+            let jump_sess = try_connect_through(jump_config, chain, rest)
+                .map_err(|e| with_message(e, format!("跳板机【 {} 】: ", jump_config.title)))?;
+            (jump_config.clone(), jump_sess)
+        }
+    };
+
+    let channel = jump_sess
+        .channel_direct_tcpip(&config.host, port, None)
+        .map_err(|e| AspenError::Network(format!(
+            "跳板机【 {} 】无法链接 {}:{}: {}", jump_config.title, config.host, config.port, e.message()
+        )))?;
+
+    bridge_channel(jump_sess, channel)
+        .map_err(|e| AspenError::Network(format!("经跳板机【 {} 】转发失败: {}", jump_config.title, e)))
+}
+
+fn find_jump(name: &str, chain: &[String]) -> Result<ServerConfig, AspenError> {
+    let configs = get_config()?;
+    let jump = match find_server_index(&configs, name) {
+        Some(index) => configs[index].clone(),
+        None => return Err(AspenError::Config(format!("未找到 proxy_jump 指定的跳板机 {}!", name))),
+    };
+
+    if chain.contains(&jump.title) {
+        return Err(AspenError::Config(format!(
+            "proxy_jump 存在循环引用: {} -> {}", chain.join(" -> "), jump.title
+        )));
+    }
+
+    Ok(jump)
+}
+
+// 在错误信息前加上前缀,保留错误类型
+fn with_message(e: AspenError, prefix: String) -> AspenError {
+    match e {
+        AspenError::Network(message) => AspenError::Network(prefix + &message),
+        AspenError::HostKey(message) => AspenError::HostKey(prefix + &message),
+        AspenError::Auth(message) => AspenError::Auth(prefix + &message),
+        other => other,
+    }
+}
+
+// 按服务器配置的顺序依次尝试 agent、私钥、密码认证
+fn try_authenticate(sess: &Session, config: &ServerConfig) -> Result<(), AspenError> {
+    let methods = config.auth_methods();
+    if methods.is_empty() {
+        return Err(AspenError::Config("未配置任何认证方式(password、identity_file 或 use_agent)!".to_string()));
+    }
+
+    let mut errors: Vec<String> = Vec::new();
+    for method in methods {
+        let result = match method {
+            AuthMethod::Agent => sess.userauth_agent(&config.username),
+            AuthMethod::Key => {
+                let identity = config.identity_path().unwrap();
+                if !identity.exists() {
+                    errors.push(format!("key: 私钥文件 {} 不存在", identity.display()));
+                    continue;
+                }
+                sess.userauth_pubkey_file(&config.username, None, &identity, config.passphrase.as_deref())
+            }
+            AuthMethod::Password => sess.userauth_password(&config.username, &config.password),
+        };
+
+        match result {
+            Ok(_) if sess.authenticated() => return Ok(()),
+            Ok(_) => errors.push(format!("{}: 认证未通过", method.name())),
+            Err(e) => errors.push(format!("{}: {}", method.name(), e.message())),
+        }
+    }
+
+    Err(AspenError::Auth(format!("身份认证失败! ({})", errors.join("; "))))
+}
+
+/**
+ * 非阻塞模式下重复执行ssh2操作,直到不再返回 EAGAIN
+ */
+pub fn retry_blocking<T>(mut f: impl FnMut() -> Result<T, ssh2::Error>) -> Result<T, ssh2::Error> {
+    loop {
+        match f() {
+            Err(e) if is_would_block(&e) => {
+                thread::sleep(Duration::from_millis(1));
+            }
+            result => return result,
+        }
+    }
+}
+
+/**
+ * 非阻塞模式下的"稍后重试"错误
+ */
+pub fn is_would_block(e: &ssh2::Error) -> bool {
+    e.code() == ErrorCode::Session(LIBSSH2_ERROR_EAGAIN)
+}
@@ -0,0 +1,322 @@
+use std::{
+    env,
+    io::{self, stdout, Read, Write},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+#[cfg(unix)]
+use std::time::Instant;
+use colored::Colorize;
+use crossterm::terminal::{self, disable_raw_mode, enable_raw_mode};
+use ssh2::{Channel, PtyModes};
+
+#[cfg(target_os = "windows")]
+use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::config::ServerConfig;
+use crate::ssh_module::guard::PinnedBanner;
+use crate::ssh_module::session::{connect_session, retry_blocking};
+
+// 本地终端向远程 PTY 转发的输入
+enum Input {
+    Data(Vec<u8>),
+    #[cfg(target_os = "windows")]
+    Resize(u16, u16),
+}
+
+// 终端 raw 模式守卫,离开作用域(包括 panic)时恢复终端
+struct RawModeGuard;
+
+impl RawModeGuard {
+    fn enable() -> Result<RawModeGuard> {
+        if enable_raw_mode().is_err() {
+            return Err(AspenError::Io("终端切换 raw 模式失败！".to_string()));
+        }
+
+        Ok(RawModeGuard)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
+
+/**
+ * 使用ssh2打开交互式shell(跨平台实现,不依赖 expect 等外部程序)
+ */
+pub fn open_shell(config: &ServerConfig) -> Result<()> {
+    println!("\n[Aspen Waiting] ==> 正在登录【 {} 】，请稍等...", config.title.green());
+
+    let sess = connect_session(config)?;
+
+    let mut pty_modes = PtyModes::new();
+    pty_modes.set_u32(ssh2::PtyModeOpcode::TTY_OP_OSPEED, 115200);
+    pty_modes.set_u32(ssh2::PtyModeOpcode::TTY_OP_ISPEED, 115200);
+
+    let mut channel = sess
+        .channel_session()
+        .map_err(|_| AspenError::Remote("与主机会话通道建立失败".to_string()))?;
+
+    // 远程 PTY 的初始大小与本地终端保持一致,固定环境横幅时少一行
+    let size = terminal::size().unwrap_or((80, 24));
+    let mut banner = PinnedBanner::pin(config, size);
+    let pty_size = banner.as_ref().map(PinnedBanner::pty_size).unwrap_or(size);
+    let term = env::var("TERM").unwrap_or_else(|_| "xterm-256color".to_string());
+
+    channel
+        .request_pty(&term, Some(pty_modes), Some((pty_size.0 as u32, pty_size.1 as u32, 0, 0)))
+        .map_err(|_| AspenError::Remote("与主机会话通道请求PTY失败！".to_string()))?;
+
+    channel.shell().map_err(|_| AspenError::Remote("启动SSH失败！".to_string()))?;
+
+    channel
+        .handle_extended_data(ssh2::ExtendedData::Merge)
+        .map_err(|e| AspenError::Remote(e.message().to_string()))?;
+
+    println!("\n {} \n", "Login Successful!!!".green());
+
+    let result = {
+        let _raw_mode = RawModeGuard::enable()?;
+
+        // 非阻塞模式最后设置,避免实例化操作链接会阻塞
+        sess.set_blocking(false);
+
+        let input = spawn_input_reader();
+        pump_shell(&mut channel, &input, size, &mut banner)
+    };
+
+    sess.set_blocking(true);
+    drop(banner);
+
+    if let Err(e) = result {
+        return Err(AspenError::Remote(format!("主机会话异常中断: {}", e)));
+    }
+
+    let _ = channel.close();
+    let _ = channel.wait_close();
+
+    println!("\n[Aspen Success] ==> 您已退出【 {} 】\n", config.title.green());
+    Ok(())
+}
+
+// 在远程输出与本地输入之间搬运数据,直到远程shell退出
+fn pump_shell(
+    channel: &mut Channel,
+    input: &Receiver<Input>,
+    size: (u16, u16),
+    banner: &mut Option<PinnedBanner>,
+) -> io::Result<()> {
+    let mut out = stdout();
+    let mut buf = [0u8; 16 * 1024];
+    #[cfg(unix)]
+    let mut size = size;
+    #[cfg(unix)]
+    let mut last_size_check = Instant::now();
+    #[cfg(not(unix))]
+    let _ = size;
+
+    loop {
+        let mut idle = true;
+
+        match channel.read(&mut buf) {
+            Ok(0) => {
+                if channel.eof() {
+                    return Ok(());
+                }
+            }
+            Ok(n) => {
+                match banner {
+                    Some(banner) => out.write_all(&banner.filter(&buf[..n]))?,
+                    None => out.write_all(&buf[..n])?,
+                }
+                out.flush()?;
+                idle = false;
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+
+        while let Ok(event) = input.try_recv() {
+            match event {
+                Input::Data(data) => write_all_nonblocking(channel, &data)?,
+                #[cfg(target_os = "windows")]
+                Input::Resize(cols, rows) => resize_pty(channel, banner, cols, rows)?,
+            }
+            idle = false;
+        }
+
+        // unix 下终端尺寸变化不会出现在输入流中,定时检查一次
+        #[cfg(unix)]
+        if last_size_check.elapsed() >= Duration::from_millis(200) {
+            last_size_check = Instant::now();
+            if let Ok(current) = terminal::size() {
+                if current != size {
+                    size = current;
+                    resize_pty(channel, banner, current.0, current.1)?;
+                }
+            }
+        }
+
+        if idle {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
+
+// 同步本地终端尺寸到远程 PTY,固定横幅时先重绘横幅,远程 PTY 少一行
+fn resize_pty(channel: &mut Channel, banner: &mut Option<PinnedBanner>, cols: u16, rows: u16) -> io::Result<()> {
+    let (cols, rows) = match banner {
+        Some(banner) => {
+            let mut out = stdout();
+            out.write_all(&banner.resize((cols, rows)))?;
+            out.flush()?;
+            banner.pty_size()
+        }
+        None => (cols, rows),
+    };
+    retry_blocking(|| channel.request_pty_size(cols as u32, rows as u32, None, None))?;
+    Ok(())
+}
+
+// 非阻塞模式下完整写入数据
+fn write_all_nonblocking(channel: &mut Channel, mut data: &[u8]) -> io::Result<()> {
+    while !data.is_empty() {
+        match channel.write(data) {
+            Ok(n) => data = &data[n..],
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(1)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    loop {
+        match channel.flush() {
+            Ok(_) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(1)),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// unix 下 raw 模式的标准输入已经是终端产生的原始字节(含全部转义序列),直接转发
+#[cfg(unix)]
+fn spawn_input_reader() -> Receiver<Input> {
+    let (tx, rx): (Sender<Input>, Receiver<Input>) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        let mut buf = [0u8; 4096];
+        loop {
+            match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if tx.send(Input::Data(buf[..n].to_vec())).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+// windows 下只能拿到按键事件,需要还原为终端转义序列
+#[cfg(target_os = "windows")]
+fn spawn_input_reader() -> Receiver<Input> {
+    let (tx, rx): (Sender<Input>, Receiver<Input>) = mpsc::channel();
+
+    thread::spawn(move || {
+        loop {
+            let input = match read() {
+                Ok(Event::Key(e)) if matches!(e.kind, KeyEventKind::Press | KeyEventKind::Repeat) => {
+                    Input::Data(key_to_bytes(&e))
+                }
+                Ok(Event::Paste(text)) => Input::Data(text.into_bytes()),
+                Ok(Event::Resize(cols, rows)) => Input::Resize(cols, rows),
+                Ok(_) => continue,
+                Err(_) => break,
+            };
+
+            if tx.send(input).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+// 按键事件转换为 xterm 兼容的字节序列
+#[cfg(target_os = "windows")]
+fn key_to_bytes(e: &KeyEvent) -> Vec<u8> {
+    let ctrl = e.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = e.modifiers.contains(KeyModifiers::ALT);
+    let shift = e.modifiers.contains(KeyModifiers::SHIFT);
+
+    // xterm 修饰键参数: 1 + shift(1) + alt(2) + ctrl(4)
+    let modifier = 1 + shift as u8 + (alt as u8) * 2 + (ctrl as u8) * 4;
+    let csi = |code: &str| -> Vec<u8> {
+        if modifier > 1 {
+            format!("\x1b[1;{}{}", modifier, code).into_bytes()
+        } else {
+            format!("\x1b[{}", code).into_bytes()
+        }
+    };
+    let tilde = |num: u8| -> Vec<u8> {
+        if modifier > 1 {
+            format!("\x1b[{};{}~", num, modifier).into_bytes()
+        } else {
+            format!("\x1b[{}~", num).into_bytes()
+        }
+    };
+
+    let mut bytes = match e.code {
+        KeyCode::Char(c) if ctrl => match c {
+            'a'..='z' | 'A'..='Z' => vec![(c.to_ascii_lowercase() as u8) & 0x1f],
+            ' ' | '@' | '2' => vec![0],
+            '[' | '3' => vec![0x1b],
+            '\\' | '4' => vec![0x1c],
+            ']' | '5' => vec![0x1d],
+            '^' | '6' => vec![0x1e],
+            '_' | '-' | '7' => vec![0x1f],
+            '?' | '8' => vec![0x7f],
+            _ => c.to_string().into_bytes(),
+        },
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => return csi("A"),
+        KeyCode::Down => return csi("B"),
+        KeyCode::Right => return csi("C"),
+        KeyCode::Left => return csi("D"),
+        KeyCode::Home => return csi("H"),
+        KeyCode::End => return csi("F"),
+        KeyCode::Insert => return tilde(2),
+        KeyCode::Delete => return tilde(3),
+        KeyCode::PageUp => return tilde(5),
+        KeyCode::PageDown => return tilde(6),
+        KeyCode::F(n) => match n {
+            1..=4 if modifier == 1 => format!("\x1bO{}", (b'P' + n - 1) as char).into_bytes(),
+            1..=4 => csi(&((b'P' + n - 1) as char).to_string()),
+            5 => tilde(15),
+            6..=10 => tilde(n + 11),
+            11..=12 => tilde(n + 12),
+            _ => vec![],
+        },
+        _ => vec![],
+    };
+
+    // Alt 组合键以 ESC 作为前缀
+    if alt && !bytes.is_empty() && !matches!(e.code, KeyCode::F(_)) {
+        bytes.insert(0, 0x1b);
+    }
+
+    bytes
+}
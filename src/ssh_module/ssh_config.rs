@@ -0,0 +1,316 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::ssh_module::config::expand_home;
+use crate::ssh_module::known_hosts::wildcard_match;
+
+// Include 嵌套的最大层数,防止互相包含导致死循环
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/**
+ * OpenSSH 配置中的一台主机(已合并匹配到的通配块)
+ */
+#[derive(Debug, Clone)]
+pub struct SshHost {
+    // Host 行中的第一个名称
+    pub name: String,
+    // 同一 Host 行中的其余名称
+    pub aliases: Vec<String>,
+    pub host_name: String,
+    pub port: u32,
+    pub user: Option<String>,
+    pub identity_file: Option<String>,
+    // ProxyJump 依次经过的各跳(去掉 user@ 与 :port)
+    pub proxy_jump: Vec<String>,
+}
+
+// 一个 Host 或 Match 块,Match 块不做条件判断,patterns 为 None 表示不匹配任何主机
+struct Block {
+    patterns: Option<Vec<String>>,
+    options: Vec<(String, String)>,
+}
+
+impl Block {
+    fn matches(&self, name: &str) -> bool {
+        let patterns = match &self.patterns {
+            Some(patterns) => patterns,
+            None => return false,
+        };
+
+        let mut matched = false;
+        for pattern in patterns {
+            if let Some(negated) = pattern.strip_prefix('!') {
+                if wildcard_match(negated, name) {
+                    return false;
+                }
+            } else if wildcard_match(pattern, name) {
+                matched = true;
+            }
+        }
+
+        matched
+    }
+}
+
+/**
+ * 解析 OpenSSH 配置文件,返回其中全部具体主机(不含通配符的 Host 名称)
+ * 与 ssh 相同,每个选项取第一个匹配块中的值,通配块(如 Host *)中的选项作用于匹配到的主机
+ */
+pub fn parse_ssh_config(path: &Path) -> Result<Vec<SshHost>, String> {
+    let mut blocks: Vec<Block> = Vec::new();
+    parse_file(path, Some(vec!["*".to_string()]), &mut blocks, 0)?;
+
+    // 按出现顺序收集具体主机,同一 Host 行的名称合并为一台
+    let mut names: Vec<Vec<String>> = Vec::new();
+    for block in &blocks {
+        let concrete: Vec<String> = block
+            .patterns
+            .iter()
+            .flatten()
+            .filter(|pattern| !pattern.starts_with('!') && !pattern.contains(['*', '?']))
+            .filter(|pattern| !names.iter().flatten().any(|name| name == *pattern))
+            .cloned()
+            .collect();
+        if !concrete.is_empty() {
+            names.push(concrete);
+        }
+    }
+
+    Ok(names.into_iter().map(|names| resolve_host(&blocks, names)).collect())
+}
+
+// 读取单个文件,文件开头(首个 Host 之前)的选项沿用 Include 所在块的条件
+fn parse_file(path: &Path, patterns: Option<Vec<String>>, blocks: &mut Vec<Block>, depth: usize) -> Result<(), String> {
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err(format!("Include 嵌套超过 {} 层: {}", MAX_INCLUDE_DEPTH, path.display()));
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+
+    blocks.push(Block { patterns: patterns.clone(), options: Vec::new() });
+    let mut current = patterns;
+
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, args) = split_line(line);
+        if args.is_empty() {
+            return Err(format!("{} 第 {} 行缺少参数: {}", path.display(), number + 1, line));
+        }
+
+        match keyword.as_str() {
+            "host" => {
+                current = Some(args);
+                blocks.push(Block { patterns: current.clone(), options: Vec::new() });
+            }
+            "match" => {
+                current = None;
+                blocks.push(Block { patterns: None, options: Vec::new() });
+            }
+            "include" => {
+                for pattern in &args {
+                    for file in include_files(pattern)? {
+                        parse_file(&file, current.clone(), blocks, depth + 1)?;
+                    }
+                }
+                // Include 之后的选项仍属于原来的块
+                blocks.push(Block { patterns: current.clone(), options: Vec::new() });
+            }
+            _ => blocks.last_mut().unwrap().options.push((keyword, args.join(" "))),
+        }
+    }
+
+    Ok(())
+}
+
+// 与 ssh 一致,相对路径基于 ~/.ssh,支持通配符
+fn include_files(pattern: &str) -> Result<Vec<std::path::PathBuf>, String> {
+    let path = if pattern.starts_with('~') || Path::new(pattern).is_absolute() {
+        expand_home(pattern)
+    } else {
+        expand_home("~/.ssh").join(pattern)
+    };
+
+    let mut files: Vec<std::path::PathBuf> = glob::glob(&path.to_string_lossy())
+        .map_err(|e| format!("Include {} 格式错误: {}", pattern, e))?
+        .flatten()
+        .filter(|file| file.is_file())
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+// 拆分 关键字 与 参数,关键字不区分大小写,支持 "Key=Value" 与双引号
+fn split_line(line: &str) -> (String, Vec<String>) {
+    let split_at = line.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(line.len());
+    let keyword = line[..split_at].to_lowercase();
+    let rest = line[split_at..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    (keyword, args)
+}
+
+fn resolve_host(blocks: &[Block], mut names: Vec<String>) -> SshHost {
+    let name = names.remove(0);
+
+    // 每个选项取第一次出现的值
+    let mut options: HashMap<&str, &str> = HashMap::new();
+    for block in blocks.iter().filter(|block| block.matches(&name)) {
+        for (keyword, value) in &block.options {
+            options.entry(keyword.as_str()).or_insert(value.as_str());
+        }
+    }
+
+    let host_name = options.get("hostname").map(|value| value.replace("%h", &name)).unwrap_or_else(|| name.clone());
+    let port = options.get("port").and_then(|value| value.parse::<u32>().ok()).unwrap_or(22);
+    let user = options.get("user").map(|value| value.to_string());
+    let identity_file = options.get("identityfile").map(|value| value.replace("%d", &expand_home("~").to_string_lossy()));
+    let proxy_jump = options.get("proxyjump").map(|value| jump_hops(value)).unwrap_or_default();
+
+    SshHost { name, aliases: names, host_name, port, user, identity_file, proxy_jump }
+}
+
+// ProxyJump 可写多跳(逗号分隔),按经过的顺序返回每一跳的主机名
+fn jump_hops(value: &str) -> Vec<String> {
+    if value.eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+
+    value
+        .split(',')
+        .map(|hop| {
+            let hop = hop.trim();
+            let hop = hop.rsplit_once('@').map(|(_, host)| host).unwrap_or(hop);
+            match hop.rsplit_once(':') {
+                Some((host, port)) if port.parse::<u16>().is_ok() => host.to_string(),
+                _ => hop.to_string(),
+            }
+        })
+        .filter(|hop| !hop.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{path::PathBuf, process};
+
+    // 把配置写入临时目录并解析
+    fn parse(name: &str, files: &[(&str, &str)]) -> Vec<SshHost> {
+        let dir = std::env::temp_dir().join(format!("aspen-ssh-config-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content.replace("$DIR", &dir.to_string_lossy())).unwrap();
+        }
+
+        let hosts = parse_ssh_config(&dir.join(files[0].0)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        hosts
+    }
+
+    fn find<'a>(hosts: &'a [SshHost], name: &str) -> &'a SshHost {
+        hosts.iter().find(|host| host.name == name).unwrap()
+    }
+
+    #[test]
+    fn split_line_handles_separators_and_quotes() {
+        assert_eq!(split_line("HostName 10.0.0.1"), ("hostname".to_string(), vec!["10.0.0.1".to_string()]));
+        assert_eq!(split_line("Port=2222"), ("port".to_string(), vec!["2222".to_string()]));
+        assert_eq!(split_line("Port = 2222"), ("port".to_string(), vec!["2222".to_string()]));
+        assert_eq!(split_line("User\t\"deploy user\""), ("user".to_string(), vec!["deploy user".to_string()]));
+        assert_eq!(
+            split_line("Host web1 web-*  !web-test"),
+            ("host".to_string(), vec!["web1".to_string(), "web-*".to_string(), "!web-test".to_string()])
+        );
+        assert_eq!(split_line("ForwardAgent"), ("forwardagent".to_string(), Vec::<String>::new()));
+    }
+
+    #[test]
+    fn jump_hops_strips_user_and_port() {
+        assert_eq!(jump_hops("admin@bastion:2222,inner"), vec!["bastion", "inner"]);
+        assert_eq!(jump_hops("bastion"), vec!["bastion"]);
+        assert_eq!(jump_hops("none"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn first_matching_value_wins() {
+        let hosts = parse("patterns", &[("config", "\
+# 注释
+Host web1 w1
+    HostName=10.0.0.1
+    Port 2201
+
+Host web-* !web-test
+    User deploy
+    Port 2202
+
+Host web-api
+    HostName 10.0.0.2
+
+Host web-test
+
+Host *
+    User root
+    Port 22
+    IdentityFile \"%d/.ssh/id key\"
+")]);
+
+        let names: Vec<&str> = hosts.iter().map(|host| host.name.as_str()).collect();
+        assert_eq!(names, vec!["web1", "web-api", "web-test"]);
+
+        let web1 = find(&hosts, "web1");
+        assert_eq!(web1.aliases, vec!["w1"]);
+        assert_eq!((web1.host_name.as_str(), web1.port, web1.user.as_deref()), ("10.0.0.1", 2201, Some("root")));
+        assert!(web1.identity_file.as_deref().unwrap().ends_with("/.ssh/id key"));
+
+        let api = find(&hosts, "web-api");
+        assert_eq!((api.host_name.as_str(), api.port, api.user.as_deref()), ("10.0.0.2", 2202, Some("deploy")));
+
+        // 被 !web-test 排除,不使用 web-* 块中的选项
+        let test = find(&hosts, "web-test");
+        assert_eq!((test.host_name.as_str(), test.port, test.user.as_deref()), ("web-test", 22, Some("root")));
+    }
+
+    #[test]
+    fn parses_multi_hop_jumps_and_includes() {
+        let hosts = parse("jumps", &[
+            ("config", "Include $DIR/hosts.conf\n\nHost app\n    ProxyJump=\"admin@bastion:2222,inner\"\n"),
+            ("hosts.conf", "Host bastion\n    HostName 1.1.1.1\nHost inner\n    HostName 10.0.0.5\n    ProxyJump none\n"),
+        ]);
+
+        assert_eq!(find(&hosts, "app").proxy_jump, vec!["bastion", "inner"]);
+        assert!(find(&hosts, "inner").proxy_jump.is_empty());
+        assert_eq!(find(&hosts, "bastion").host_name, "1.1.1.1");
+    }
+
+    #[test]
+    fn missing_argument_is_an_error() {
+        let dir: PathBuf = std::env::temp_dir().join(format!("aspen-ssh-config-error-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config"), "Host a\n    HostName\n").unwrap();
+
+        let error = parse_ssh_config(&dir.join("config")).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(error.contains("第 2 行缺少参数"));
+    }
+}
@@ -0,0 +1,183 @@
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::command::pick_server;
+use crate::ssh_module::config::{find_server_index, ServerConfig};
+use crate::ssh_module::selector::Selector;
+
+pub enum TargetMatch {
+    One(usize),
+    Many(Vec<usize>),
+    None,
+}
+
+/**
+ * 按 序号 -> 标题 -> 别名 -> 模糊匹配 的顺序查找服务器
+ */
+pub fn match_target(configs: &[ServerConfig], query: &str) -> TargetMatch {
+    let query = query.trim();
+
+    // 序号、标题、别名精确匹配
+    if let Some(index) = find_server_index(configs, query) {
+        return TargetMatch::One(index);
+    }
+
+    // 纯数字只当作序号,超出范围时不再模糊匹配
+    if !query.is_empty() && query.chars().all(|c| c.is_ascii_digit()) {
+        return TargetMatch::None;
+    }
+
+    // 标题、别名忽略大小写匹配
+    let lower = query.to_lowercase();
+    let exact: Vec<usize> = positions(configs, |config| {
+        config.title.to_lowercase() == lower || config.aliases.iter().any(|alias| alias.to_lowercase() == lower)
+    });
+    if exact.len() == 1 {
+        return TargetMatch::One(exact[0]);
+    }
+
+    // 标题、主机、分类、别名中包含关键字的优先,其次按字符顺序的模糊匹配
+    let contains = positions(configs, |config| search_fields(config).any(|field| field.contains(&lower)));
+    let candidates = if contains.is_empty() {
+        positions(configs, |config| search_fields(config).any(|field| is_subsequence(&lower, &field)))
+    } else {
+        contains
+    };
+
+    match candidates.len() {
+        0 => TargetMatch::None,
+        1 => TargetMatch::One(candidates[0]),
+        _ => TargetMatch::Many(candidates),
+    }
+}
+
+/**
+ * 解析目标服务器,只保留满足筛选条件(--category、--host、--tag)的结果,匹配到多个时在候选中交互选择
+ * 序号始终对应完整列表,与 all 命令中的 ID 一致
+ */
+pub fn resolve_target(configs: &[ServerConfig], query: &str, filter: &Selector) -> Result<ServerConfig> {
+    let indexes: Vec<usize> = match match_target(configs, query) {
+        TargetMatch::One(index) => vec![index],
+        TargetMatch::Many(indexes) => indexes,
+        TargetMatch::None => Vec::new(),
+    };
+    let candidates: Vec<ServerConfig> = indexes
+        .iter()
+        .map(|index| configs[*index].clone())
+        .filter(|config| filter.matches(config))
+        .collect();
+
+    let config = match candidates.len() {
+        0 if filter.is_empty() => return Err(AspenError::NotFound(format!("未找到与 {} 匹配的服务器!", query))),
+        0 => return Err(AspenError::NotFound(format!("未找到与 {} 匹配且满足筛选条件的服务器!", query))),
+        1 => candidates[0].clone(),
+        _ => {
+            let hint = format!("【 {} 】匹配到 {} 台服务器", query, candidates.len());
+            let index = pick_server(&candidates, Some(&hint))?;
+            candidates[index].clone()
+        }
+    };
+
+    Ok(config)
+}
+
+fn positions(configs: &[ServerConfig], predicate: impl Fn(&ServerConfig) -> bool) -> Vec<usize> {
+    configs
+        .iter()
+        .enumerate()
+        .filter(|(_, config)| predicate(config))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// 参与模糊匹配的字段(小写)
+fn search_fields(config: &ServerConfig) -> impl Iterator<Item = String> + '_ {
+    [&config.title, &config.host, &config.category]
+        .into_iter()
+        .chain(config.aliases.iter())
+        .map(|field| field.to_lowercase())
+}
+
+// needle 的字符是否按顺序出现在 haystack 中
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut chars = haystack.chars();
+    needle.chars().all(|c| chars.any(|h| h == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(title: &str, host: &str, aliases: &[&str]) -> ServerConfig {
+        serde_json::from_value(serde_json::json!({
+            "title": title,
+            "category": "CentOS",
+            "host": host,
+            "port": 22,
+            "username": "root",
+            "remark": "",
+            "aliases": aliases,
+        }))
+        .unwrap()
+    }
+
+    fn servers() -> Vec<ServerConfig> {
+        vec![
+            server("Web1", "10.0.0.11", &["w1"]),
+            server("Web2", "10.0.0.12", &[]),
+            server("Database", "10.0.1.20", &["db"]),
+        ]
+    }
+
+    fn matched(query: &str) -> Vec<usize> {
+        match match_target(&servers(), query) {
+            TargetMatch::One(index) => vec![index],
+            TargetMatch::Many(indexes) => indexes,
+            TargetMatch::None => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matches_index_title_and_alias() {
+        assert_eq!(matched("2"), vec![1]);
+        assert_eq!(matched(" 3 "), vec![2]);
+        assert_eq!(matched("Web1"), vec![0]);
+        assert_eq!(matched("db"), vec![2]);
+    }
+
+    #[test]
+    fn out_of_range_index_is_not_fuzzy_matched() {
+        // "12" 是 10.0.0.12 的子串,也不能当作模糊匹配
+        assert!(matches!(match_target(&servers(), "12"), TargetMatch::None));
+        assert!(matches!(match_target(&servers(), "0"), TargetMatch::None));
+    }
+
+    #[test]
+    fn matches_case_insensitive_contains_and_subsequence() {
+        assert_eq!(matched("WEB2"), vec![1]);
+        assert_eq!(matched("W1"), vec![0]);
+        assert_eq!(matched("datab"), vec![2]);
+        assert_eq!(matched("1.20"), vec![2]);
+        assert_eq!(matched("dtbs"), vec![2]);
+        assert!(matched("xyz").is_empty());
+    }
+
+    #[test]
+    fn ambiguous_query_returns_all_candidates() {
+        assert!(matches!(match_target(&servers(), "web"), TargetMatch::Many(ref indexes) if indexes == &vec![0, 1]));
+        assert!(matches!(match_target(&servers(), "10.0.0"), TargetMatch::Many(ref indexes) if indexes.len() == 2));
+    }
+
+    #[test]
+    fn resolve_target_applies_filter() {
+        let configs = servers();
+        let all = Selector::parse("all").unwrap();
+
+        assert_eq!(resolve_target(&configs, "db", &all).unwrap().title, "Database");
+        assert!(matches!(resolve_target(&configs, "12", &all), Err(AspenError::NotFound(_))));
+        assert!(matches!(resolve_target(&configs, "nothing", &all), Err(AspenError::NotFound(_))));
+
+        // 筛选条件把多个候选收窄为一个时不再交互选择
+        let host = Selector::parse("host=10.0.0.12").unwrap();
+        assert_eq!(resolve_target(&configs, "web", &host).unwrap().title, "Web2");
+        assert!(matches!(resolve_target(&configs, "db", &host), Err(AspenError::NotFound(_))));
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    net::TcpListener,
+    sync::mpsc::{self, Sender},
+    thread,
+};
+use clap::ArgMatches;
+use colored::Colorize;
+use prettytable::{row, Table};
+
+use crate::aspen_module::error::{AspenError, Result};
+use crate::ssh_module::command::table_format;
+use crate::ssh_module::config::{bind_address, get_config, ForwardConfig, RemoteForwardConfig};
+use crate::ssh_module::guard::confirm_dangerous;
+use crate::ssh_module::selector::Selector;
+use crate::ssh_module::session::connect_session;
+use crate::ssh_module::target::resolve_target;
+use crate::tunnel_module::pump::{ConnectRequest, Pump};
+use crate::tunnel_module::socks::{spawn_socks_server, SocksAuth};
+use crate::vault_module::store::resolve_secret;
+
+/**
+ * tunnel 命令实现: 打开服务器配置中的本地端口转发,直到 Ctrl-C
+ */
+pub fn impl_tunnel_action(matches: &ArgMatches) -> Result<()> {
+    let configs = get_config()?;
+    let config = resolve_target(&configs, matches.get_one::<String>("target").unwrap(), &Selector::from_filter_args(matches)?)?;
+
+    let forwards: Vec<ForwardConfig> = match matches.get_one::<String>("name") {
+        Some(name) => config.forwards.iter().filter(|forward| forward.name == *name).cloned().collect(),
+        None => config.forwards.clone(),
+    };
+    if forwards.is_empty() {
+        let message = match matches.get_one::<String>("name") {
+            Some(name) => format!("【 {} 】中没有名为 {} 的端口转发!", config.title, name),
+            None => format!("【 {} 】尚未配置端口转发(forwards)!", config.title),
+        };
+        return Err(AspenError::NotFound(message));
+    }
+    confirm_dangerous(&config, "端口转发", matches.get_flag("yes-i-am-sure"))?;
+
+    // 先监听本地端口,端口被占用时无需再链接服务器
+    let listeners: Vec<(ForwardConfig, TcpListener)> = forwards
+        .into_iter()
+        .map(|forward| {
+            let listener = bind_listener(&forward.bind_address())?;
+            Ok((forward, listener))
+        })
+        .collect::<Result<Vec<(ForwardConfig, TcpListener)>>>()?;
+
+    println!("\n[Aspen Waiting] ==> 正在链接【 {} 】，请稍等...", config.title.green());
+    let sess = connect_session(&config)?;
+
+    let (sender, receiver) = mpsc::channel::<ConnectRequest>();
+    print_forwards_table(&listeners);
+    for (forward, listener) in listeners {
+        spawn_acceptor(forward, listener, sender.clone());
+    }
+
+    println!("\n[Aspen Success] ==> 【 {} 】端口转发已开启,按 Ctrl-C 退出\n", config.title.green());
+
+    Pump::new(sess, receiver).run().map_err(AspenError::Remote)
+}
+
+/**
+ * rtunnel 命令实现: 在服务器上监听端口并转发到本地地址,直到 Ctrl-C
+ * 命令行指定 -R 时只使用命令行中的转发,否则使用服务器配置中的 remote_forwards
+ */
+pub fn impl_rtunnel_action(matches: &ArgMatches) -> Result<()> {
+    let configs = get_config()?;
+    let config = resolve_target(&configs, matches.get_one::<String>("target").unwrap(), &Selector::from_filter_args(matches)?)?;
+
+    let forwards: Vec<RemoteForwardConfig> = match matches.get_many::<String>("remote") {
+        Some(values) => values
+            .map(|value| {
+                RemoteForwardConfig::parse(value).ok_or_else(|| {
+                    AspenError::Usage(format!("-R {} 格式应为 [监听地址:]端口:本地地址:本地端口", value))
+                })
+            })
+            .collect::<Result<Vec<RemoteForwardConfig>>>()?,
+        None => match matches.get_one::<String>("name") {
+            Some(name) => config.remote_forwards.iter().filter(|forward| forward.name == *name).cloned().collect(),
+            None => config.remote_forwards.clone(),
+        },
+    };
+    if forwards.is_empty() {
+        let message = match matches.get_one::<String>("name") {
+            Some(name) => format!("【 {} 】中没有名为 {} 的远程端口转发!", config.title, name),
+            None => format!("【 {} 】尚未配置远程端口转发(remote_forwards),也可使用 -R 指定!", config.title),
+        };
+        return Err(AspenError::NotFound(message));
+    }
+    confirm_dangerous(&config, "远程端口转发", matches.get_flag("yes-i-am-sure"))?;
+
+    println!("\n[Aspen Waiting] ==> 正在链接【 {} 】，请稍等...", config.title.green());
+    let sess = connect_session(&config)?;
+
+    // 远程转发没有本地发起的链接请求
+    let (_, receiver) = mpsc::channel::<ConnectRequest>();
+    let mut pump = Pump::new(sess, receiver);
+
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(row!["Name", "Remote", "Local"]);
+    for forward in &forwards {
+        let (host, port) = forward.remote_listen().unwrap();
+        let bound_port = pump
+            .listen_remote(host.as_deref(), port, &forward.local_target())
+            .map_err(AspenError::Remote)?;
+        let remote = format!("{}:{}", host.as_deref().unwrap_or("localhost"), bound_port);
+        table.add_row(row![forward.name, remote, forward.local_target()]);
+    }
+    table.printstd();
+
+    println!("\n[Aspen Success] ==> 【 {} 】远程端口转发已开启,按 Ctrl-C 退出\n", config.title.green());
+
+    pump.run().map_err(AspenError::Remote)
+}
+
+/**
+ * socks 命令实现: 在本地运行 SOCKS5 代理,每个 CONNECT 请求经服务器转发,直到 Ctrl-C
+ */
+pub fn impl_socks_action(matches: &ArgMatches) -> Result<()> {
+    let configs = get_config()?;
+    let config = resolve_target(&configs, matches.get_one::<String>("target").unwrap(), &Selector::from_filter_args(matches)?)?;
+    let address = bind_address(matches.get_one::<String>("bind").unwrap());
+
+    let auth = match (matches.get_one::<String>("username"), matches.get_one::<String>("password")) {
+        (Some(username), Some(password)) => Some(SocksAuth {
+            username: username.clone(),
+            password: resolve_secret(&mut None, password)?,
+        }),
+        (None, None) => None,
+        _ => return Err(AspenError::Usage("--username 与 --password 需同时指定!".to_string())),
+    };
+    confirm_dangerous(&config, "SOCKS 代理", matches.get_flag("yes-i-am-sure"))?;
+
+    let listener = bind_listener(&address)?;
+    let local = listener.local_addr().map(|addr| addr.to_string()).unwrap_or(address);
+
+    println!("\n[Aspen Waiting] ==> 正在链接【 {} 】，请稍等...", config.title.green());
+    let sess = connect_session(&config)?;
+
+    let (sender, receiver) = mpsc::channel::<ConnectRequest>();
+    let with_auth = auth.is_some();
+    spawn_socks_server(listener, auth, sender);
+
+    println!(
+        "\n[Aspen Success] ==> 【 {} 】SOCKS5 代理已监听 {}{},按 Ctrl-C 退出\n",
+        config.title.green(), local.green(), if with_auth { " (用户名密码认证)" } else { "" }
+    );
+
+    Pump::new(sess, receiver).run().map_err(AspenError::Remote)
+}
+
+/**
+ * 监听本地地址
+ */
+pub fn bind_listener(address: &str) -> Result<TcpListener> {
+    TcpListener::bind(address).map_err(|e| AspenError::Io(format!("监听本地地址 {} 失败: {}", address, e)))
+}
+
+// 每个本地端口一个线程接受链接,交给转发线程打开通道
+fn spawn_acceptor(forward: ForwardConfig, listener: TcpListener, sender: Sender<ConnectRequest>) {
+    thread::spawn(move || {
+        for client in listener.incoming().flatten() {
+            let peer = client.peer_addr().map(|addr| addr.to_string()).unwrap_or_default();
+            let label = format!("{} -> {}:{} ({})", forward.bind_address(), forward.remote_host, forward.remote_port, peer);
+
+            if sender.send(ConnectRequest::new(client, &forward.remote_host, forward.remote_port, label)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn print_forwards_table(listeners: &[(ForwardConfig, TcpListener)]) {
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(row!["Name", "Local", "Remote"]);
+
+    for (forward, listener) in listeners {
+        let local = listener.local_addr().map(|addr| addr.to_string()).unwrap_or_else(|_| forward.bind_address());
+        table.add_row(row![forward.name, local, format!("{}:{}", forward.remote_host, forward.remote_port)]);
+    }
+
+    table.printstd();
+}
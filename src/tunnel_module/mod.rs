@@ -0,0 +1,3 @@
+pub mod pump;
+pub mod socks;
+pub mod command;
@@ -0,0 +1,412 @@
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+use colored::Colorize;
+use ssh2::{Channel, Listener, Session};
+
+use crate::ssh_module::session::{is_would_block, retry_blocking};
+
+// 单个方向缓冲的上限,超过后暂停读取,等待对端消费
+const MAX_BUFFERED: usize = 256 * 1024;
+
+// 远程转发链接本地地址的超时时间
+const LOCAL_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 空闲时的轮询间隔
+const IDLE_WAIT: Duration = Duration::from_millis(2);
+
+// 保活包的发送间隔(秒)
+const KEEPALIVE_SECONDS: u32 = 30;
+
+/**
+ * 请求经服务器链接 host:port,并与本地客户端的 TCP 链接对接
+ * 通道打开后向客户端写入 reply_ok,失败时写入 reply_err(普通端口转发时为空)
+ */
+pub struct ConnectRequest {
+    pub client: TcpStream,
+    pub host: String,
+    pub port: u16,
+    pub label: String,
+    pub reply_ok: Vec<u8>,
+    pub reply_err: Vec<u8>,
+}
+
+impl ConnectRequest {
+    pub fn new(client: TcpStream, host: &str, port: u16, label: String) -> ConnectRequest {
+        ConnectRequest {
+            client,
+            host: host.to_string(),
+            port,
+            label,
+            reply_ok: Vec::new(),
+            reply_err: Vec::new(),
+        }
+    }
+}
+
+// 本地 TCP 链接与 ssh 通道之间的一条双向转发
+struct Connection {
+    label: String,
+    client: TcpStream,
+    channel: Channel,
+    to_channel: Vec<u8>,
+    to_client: Vec<u8>,
+    client_eof: bool,
+    channel_eof: bool,
+    eof_sent: bool,
+    shutdown_sent: bool,
+    sent: u64,
+    received: u64,
+    opened_at: Instant,
+}
+
+// 服务器上的监听端口(远程转发),收到的链接转发到本地 local
+struct RemoteListener {
+    listener: Listener,
+    local: String,
+    label: String,
+    accepted: u64,
+}
+
+/**
+ * 在单个线程中以非阻塞方式驱动同一会话上的全部通道
+ * ssh2 会话内部有锁,多个线程各自阻塞读写通道会互相等待,因此统一由此处轮询
+ */
+pub struct Pump {
+    sess: Session,
+    requests: Receiver<ConnectRequest>,
+    listeners: Vec<RemoteListener>,
+    connections: Vec<Connection>,
+}
+
+impl Pump {
+    pub fn new(sess: Session, requests: Receiver<ConnectRequest>) -> Pump {
+        sess.set_keepalive(true, KEEPALIVE_SECONDS);
+
+        Pump { sess, requests, listeners: Vec::new(), connections: Vec::new() }
+    }
+
+    /**
+     * 在服务器上监听端口(需在 run 之前调用),返回实际监听的端口
+     */
+    pub fn listen_remote(&mut self, host: Option<&str>, port: u16, local: &str) -> Result<u16, String> {
+        let (listener, bound_port) = self
+            .sess
+            .channel_forward_listen(port, host, None)
+            .map_err(|e| format!("服务器监听端口 {} 失败: {}", port, e.message()))?;
+
+        let label = format!("{}:{} -> {}", host.unwrap_or("localhost"), bound_port, local);
+        self.listeners.push(RemoteListener { listener, local: local.to_string(), label, accepted: 0 });
+
+        Ok(bound_port)
+    }
+
+    /**
+     * 持续转发,直到会话断开
+     */
+    pub fn run(&mut self) -> Result<(), String> {
+        self.sess.set_blocking(false);
+        let mut next_keepalive = Instant::now();
+
+        loop {
+            let mut idle = self.accept_requests();
+            idle &= self.accept_remote();
+
+            let mut index = 0;
+            while index < self.connections.len() {
+                match self.connections[index].pump() {
+                    Ok(active) => {
+                        idle &= !active;
+                        if self.connections[index].finished() {
+                            let connection = self.connections.swap_remove(index);
+                            connection.close();
+                            continue;
+                        }
+                    }
+                    Err(e) => {
+                        let connection = self.connections.swap_remove(index);
+                        log_error(&connection.label, &e);
+                        connection.close();
+                        continue;
+                    }
+                }
+                index += 1;
+            }
+
+            if Instant::now() >= next_keepalive {
+                match self.sess.keepalive_send() {
+                    Ok(seconds) => next_keepalive = Instant::now() + Duration::from_secs(seconds.max(1) as u64),
+                    Err(e) if is_would_block(&e) => {}
+                    Err(e) => return Err(format!("与服务器的会话已断开: {}", e.message())),
+                }
+            }
+
+            if idle {
+                thread::sleep(IDLE_WAIT);
+            }
+        }
+    }
+
+    // 接入一条已打开的通道
+    fn add_channel(&mut self, client: TcpStream, channel: Channel, label: String) {
+        if client.set_nonblocking(true).is_err() {
+            log_error(&label, "设置非阻塞模式失败");
+            return;
+        }
+
+        log_open(&label);
+        self.connections.push(Connection::new(label, client, channel));
+    }
+
+    // 接受服务器上监听端口收到的链接,并链接到对应的本地地址,返回本轮是否空闲
+    fn accept_remote(&mut self) -> bool {
+        let mut accepted: Vec<(Channel, String, String)> = Vec::new();
+
+        for remote in self.listeners.iter_mut() {
+            match remote.listener.accept() {
+                Ok(channel) => {
+                    remote.accepted += 1;
+                    accepted.push((channel, remote.local.clone(), format!("{} #{}", remote.label, remote.accepted)));
+                }
+                Err(e) if is_would_block(&e) => {}
+                Err(e) => log_error(&remote.label, &format!("接受链接失败: {}", e.message())),
+            }
+        }
+
+        let idle = accepted.is_empty();
+        for (mut channel, local, label) in accepted {
+            match connect_local(&local) {
+                Ok(client) => self.add_channel(client, channel, label),
+                Err(e) => {
+                    log_error(&label, &format!("链接本地地址失败: {}", e));
+                    let _ = channel.close();
+                }
+            }
+        }
+
+        idle
+    }
+
+    // 为新请求打开 direct-tcpip 通道,返回本轮是否空闲
+    fn accept_requests(&mut self) -> bool {
+        let mut idle = true;
+
+        loop {
+            let mut request = match self.requests.try_recv() {
+                Ok(request) => request,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return idle,
+            };
+            idle = false;
+
+            let origin = request.client.peer_addr().ok().map(|addr| (addr.ip().to_string(), addr.port()));
+            let result = retry_blocking(|| {
+                let origin = origin.as_ref().map(|(ip, port)| (ip.as_str(), *port));
+                self.sess.channel_direct_tcpip(&request.host, request.port, origin)
+            });
+
+            match result {
+                Ok(channel) => {
+                    if !request.reply_ok.is_empty() && request.client.write_all(&request.reply_ok).is_err() {
+                        continue;
+                    }
+                    self.add_channel(request.client, channel, request.label);
+                }
+                Err(e) => {
+                    if !request.reply_err.is_empty() {
+                        let _ = request.client.write_all(&request.reply_err);
+                    }
+                    log_error(&request.label, &format!("打开通道失败: {}", e.message()));
+                }
+            }
+        }
+    }
+}
+
+impl Connection {
+    fn new(label: String, client: TcpStream, channel: Channel) -> Connection {
+        Connection {
+            label,
+            client,
+            channel,
+            to_channel: Vec::new(),
+            to_client: Vec::new(),
+            client_eof: false,
+            channel_eof: false,
+            eof_sent: false,
+            shutdown_sent: false,
+            sent: 0,
+            received: 0,
+            opened_at: Instant::now(),
+        }
+    }
+
+    // 双向搬运一次数据,返回是否有数据流动
+    fn pump(&mut self) -> Result<bool, String> {
+        let mut active = false;
+        let mut buf = [0u8; 16 * 1024];
+
+        // 客户端 -> 通道
+        if !self.client_eof && self.to_channel.len() < MAX_BUFFERED {
+            match self.client.read(&mut buf) {
+                Ok(0) => self.client_eof = true,
+                Ok(n) => {
+                    self.to_channel.extend_from_slice(&buf[..n]);
+                    active = true;
+                }
+                Err(e) if is_retryable(&e) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        if !self.to_channel.is_empty() {
+            match self.channel.write(&self.to_channel) {
+                Ok(n) => {
+                    self.to_channel.drain(..n);
+                    self.sent += n as u64;
+                    active |= n > 0;
+                }
+                Err(e) if is_retryable(&e) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        if self.client_eof && self.to_channel.is_empty() && !self.eof_sent {
+            match self.channel.send_eof() {
+                Ok(_) => self.eof_sent = true,
+                Err(e) if is_would_block(&e) => {}
+                Err(e) => return Err(e.message().to_string()),
+            }
+        }
+
+        // 通道 -> 客户端
+        if !self.channel_eof && self.to_client.len() < MAX_BUFFERED {
+            match self.channel.read(&mut buf) {
+                Ok(0) => self.channel_eof = self.channel.eof(),
+                Ok(n) => {
+                    self.to_client.extend_from_slice(&buf[..n]);
+                    active = true;
+                }
+                Err(e) if is_retryable(&e) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        if !self.to_client.is_empty() {
+            match self.client.write(&self.to_client) {
+                Ok(n) => {
+                    self.to_client.drain(..n);
+                    self.received += n as u64;
+                    active |= n > 0;
+                }
+                Err(e) if is_retryable(&e) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        if self.channel_eof && self.to_client.is_empty() && !self.shutdown_sent {
+            let _ = self.client.shutdown(Shutdown::Write);
+            self.shutdown_sent = true;
+        }
+
+        Ok(active)
+    }
+
+    fn finished(&self) -> bool {
+        self.channel_eof && self.to_client.is_empty() && self.eof_sent
+    }
+
+    fn release(&mut self) {
+        let _ = self.channel.close();
+        let _ = self.client.shutdown(Shutdown::Both);
+    }
+
+    fn close(mut self) {
+        self.release();
+
+        println!(
+            "[Aspen Tunnel] {} 已关闭  ↑ {}  ↓ {}  {:.1}s",
+            self.label, format_bytes(self.sent), format_bytes(self.received), self.opened_at.elapsed().as_secs_f64()
+        );
+    }
+}
+
+/**
+ * 将 ssh 通道桥接为本地 TCP 链接,供跳板机之后的会话作为底层链接使用
+ * 会话与通道交由后台线程驱动,直到任一端关闭
+ */
+pub fn bridge_channel(sess: Session, channel: Channel) -> io::Result<TcpStream> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let stream = TcpStream::connect(listener.local_addr()?)?;
+    let local = stream.local_addr()?;
+
+    // 只接受自己发起的链接,防止本机其他进程抢先接入
+    let client = loop {
+        let (client, peer) = listener.accept()?;
+        if peer == local {
+            break client;
+        }
+    };
+    client.set_nonblocking(true)?;
+
+    thread::spawn(move || {
+        sess.set_blocking(false);
+        let mut connection = Connection::new(String::new(), client, channel);
+
+        loop {
+            match connection.pump() {
+                Ok(_) if connection.finished() => break,
+                Ok(true) => {}
+                Ok(false) => thread::sleep(IDLE_WAIT),
+                Err(_) => break,
+            }
+        }
+        connection.release();
+    });
+
+    Ok(stream)
+}
+
+fn connect_local(address: &str) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "地址无法解析");
+
+    for addr in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, LOCAL_CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(last_error)
+}
+
+fn is_retryable(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted)
+}
+
+fn log_open(label: &str) {
+    println!("[Aspen Tunnel] {} 已链接", label);
+}
+
+fn log_error(label: &str, message: &str) {
+    eprintln!("[Aspen Tunnel] {} {}", label, message.red());
+}
+
+/**
+ * 以 B/KB/MB/GB 显示字节数
+ */
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    io::{self, Read, Write},
+    net::{Ipv4Addr, Ipv6Addr, TcpListener, TcpStream},
+    sync::mpsc::Sender,
+    thread,
+    time::Duration,
+};
+use colored::Colorize;
+
+use crate::tunnel_module::pump::ConnectRequest;
+
+const SOCKS_VERSION: u8 = 0x05;
+const AUTH_VERSION: u8 = 0x01;
+
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_PASSWORD: u8 = 0x02;
+const METHOD_UNACCEPTABLE: u8 = 0xFF;
+
+const CMD_CONNECT: u8 = 0x01;
+
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+const REPLY_SUCCEEDED: u8 = 0x00;
+const REPLY_GENERAL_FAILURE: u8 = 0x01;
+const REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const REPLY_ADDRESS_NOT_SUPPORTED: u8 = 0x08;
+
+// 握手阶段的读写超时,避免异常客户端长期占用线程
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/**
+ * SOCKS5 用户名、密码认证
+ */
+#[derive(Clone)]
+pub struct SocksAuth {
+    pub username: String,
+    pub password: String,
+}
+
+/**
+ * 接受 SOCKS5 客户端,完成握手后将 CONNECT 请求交给转发线程
+ */
+pub fn spawn_socks_server(listener: TcpListener, auth: Option<SocksAuth>, sender: Sender<ConnectRequest>) {
+    thread::spawn(move || {
+        for client in listener.incoming().flatten() {
+            let auth = auth.clone();
+            let sender = sender.clone();
+
+            // 握手是阻塞读写,每个客户端单独处理,不影响其他链接
+            thread::spawn(move || {
+                let peer = client.peer_addr().map(|addr| addr.to_string()).unwrap_or_default();
+                match handshake(client, auth.as_ref(), &peer) {
+                    Ok(request) => {
+                        let _ = sender.send(request);
+                    }
+                    Err(e) => eprintln!("[Aspen Socks] {} {}", peer, format!("握手失败: {}", e).red()),
+                }
+            });
+        }
+    });
+}
+
+fn handshake(mut client: TcpStream, auth: Option<&SocksAuth>, peer: &str) -> io::Result<ConnectRequest> {
+    client.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    client.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    // 协商认证方式
+    let [version, count] = read_array::<2>(&mut client)?;
+    if version != SOCKS_VERSION {
+        return Err(invalid("不支持的 SOCKS 版本"));
+    }
+    let methods = read_vec(&mut client, count as usize)?;
+    let method = if auth.is_some() { METHOD_PASSWORD } else { METHOD_NO_AUTH };
+    if !methods.contains(&method) {
+        client.write_all(&[SOCKS_VERSION, METHOD_UNACCEPTABLE])?;
+        return Err(invalid("客户端不支持所需的认证方式"));
+    }
+    client.write_all(&[SOCKS_VERSION, method])?;
+
+    if let Some(auth) = auth {
+        verify_password(&mut client, auth)?;
+    }
+
+    // 读取链接请求
+    let [version, command, _, address_type] = read_array::<4>(&mut client)?;
+    if version != SOCKS_VERSION {
+        return Err(invalid("不支持的 SOCKS 版本"));
+    }
+
+    let host = match address_type {
+        ATYP_IPV4 => Ipv4Addr::from(read_array::<4>(&mut client)?).to_string(),
+        ATYP_IPV6 => Ipv6Addr::from(read_array::<16>(&mut client)?).to_string(),
+        ATYP_DOMAIN => {
+            let [length] = read_array::<1>(&mut client)?;
+            String::from_utf8_lossy(&read_vec(&mut client, length as usize)?).to_string()
+        }
+        _ => {
+            client.write_all(&reply(REPLY_ADDRESS_NOT_SUPPORTED))?;
+            return Err(invalid("不支持的地址类型"));
+        }
+    };
+    let port = u16::from_be_bytes(read_array::<2>(&mut client)?);
+
+    if command != CMD_CONNECT {
+        client.write_all(&reply(REPLY_COMMAND_NOT_SUPPORTED))?;
+        return Err(invalid("只支持 CONNECT 命令"));
+    }
+
+    client.set_read_timeout(None)?;
+    client.set_write_timeout(None)?;
+
+    let label = format!("{} -> {}:{}", peer, host, port);
+    let mut request = ConnectRequest::new(client, &host, port, label);
+    request.reply_ok = reply(REPLY_SUCCEEDED);
+    request.reply_err = reply(REPLY_GENERAL_FAILURE);
+
+    Ok(request)
+}
+
+// RFC 1929 用户名、密码认证
+fn verify_password(client: &mut TcpStream, auth: &SocksAuth) -> io::Result<()> {
+    let [version, username_length] = read_array::<2>(client)?;
+    if version != AUTH_VERSION {
+        return Err(invalid("不支持的认证协议版本"));
+    }
+    let username = read_vec(client, username_length as usize)?;
+    let [password_length] = read_array::<1>(client)?;
+    let password = read_vec(client, password_length as usize)?;
+
+    if username != auth.username.as_bytes() || password != auth.password.as_bytes() {
+        client.write_all(&[AUTH_VERSION, 0x01])?;
+        return Err(invalid("用户名或密码错误"));
+    }
+
+    client.write_all(&[AUTH_VERSION, 0x00])
+}
+
+// 应答中的绑定地址固定为 0.0.0.0:0,客户端通常不使用
+fn reply(code: u8) -> Vec<u8> {
+    vec![SOCKS_VERSION, code, 0x00, ATYP_IPV4, 0, 0, 0, 0, 0, 0]
+}
+
+fn read_array<const N: usize>(client: &mut TcpStream) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    client.read_exact(&mut buf)?;
+
+    Ok(buf)
+}
+
+fn read_vec(client: &mut TcpStream, length: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; length];
+    client.read_exact(&mut buf)?;
+
+    Ok(buf)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
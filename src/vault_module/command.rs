@@ -0,0 +1,115 @@
+use clap::ArgMatches;
+use colored::Colorize;
+use prettytable::{row, Table};
+
+use crate::aspen_module::config::{get_aspen_config, write_aspen_config};
+use crate::aspen_module::error::{AspenError, Result};
+use crate::vault_module::store::{lock_vault, open_or_create_vault, prompt_secret, unlock_vault, VAULT_PREFIX};
+
+/**
+ * vault 命令实现
+ */
+pub fn impl_vault_action(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("set", sub_matches)) => vault_set_action(sub_matches),
+        Some(("get", sub_matches)) => vault_get_action(sub_matches),
+        Some(("rm", sub_matches)) => vault_rm_action(sub_matches),
+        Some(("list", _)) => vault_list_action(),
+        Some(("lock", _)) => vault_lock_action(),
+        Some(("timeout", sub_matches)) => vault_timeout_action(sub_matches),
+        _ => Err(AspenError::Usage("非法指令".to_string())),
+    }
+}
+
+// 新增或更新条目
+fn vault_set_action(matches: &ArgMatches) -> Result<()> {
+    let key = matches.get_one::<String>("key").unwrap();
+    let mut vault = open_or_create_vault()?;
+
+    // 推荐隐藏输入或通过管道传入,命令行参数会出现在 ps 与 shell 历史中
+    let value = match matches.get_one::<String>("value") {
+        Some(value) => {
+            eprintln!(
+                "\n[Aspen Warning] => {}",
+                "条目的值写在命令行中会被 ps 与 shell 历史记录,建议省略后隐藏输入或通过管道传入".yellow()
+            );
+            value.clone()
+        }
+        None => prompt_secret(&format!("请输入 {} 的值: ", key))?,
+    };
+
+    vault.entries.insert(key.clone(), value);
+    vault.save()?;
+
+    println!("\n[Aspen Success] ==> 已保存,服务器配置中可使用 {} 引用\n", format!("{}{}", VAULT_PREFIX, key).green());
+    Ok(())
+}
+
+// 查看条目的值
+fn vault_get_action(matches: &ArgMatches) -> Result<()> {
+    let key = matches.get_one::<String>("key").unwrap();
+    let vault = unlock_vault()?;
+
+    match vault.entries.get(key) {
+        Some(value) => {
+            println!("{}", value);
+            Ok(())
+        }
+        None => Err(AspenError::NotFound(format!("保险库中不存在条目 {}!", key))),
+    }
+}
+
+// 删除条目
+fn vault_rm_action(matches: &ArgMatches) -> Result<()> {
+    let key = matches.get_one::<String>("key").unwrap();
+    let mut vault = unlock_vault()?;
+
+    if vault.entries.remove(key).is_none() {
+        return Err(AspenError::NotFound(format!("保险库中不存在条目 {}!", key)));
+    }
+    vault.save()?;
+
+    println!("\n[Aspen Success] ==> 已删除条目 {}\n", key.green());
+    Ok(())
+}
+
+// 列出全部条目名称(不显示值)
+fn vault_list_action() -> Result<()> {
+    let vault = unlock_vault()?;
+
+    let mut table = Table::new();
+    table.set_titles(row!["ID", "Key", "Reference"]);
+    for (index, key) in vault.entries.keys().enumerate() {
+        table.add_row(row![(index + 1), key, format!("{}{}", VAULT_PREFIX, key)]);
+    }
+
+    table.printstd();
+    Ok(())
+}
+
+// 立即锁定保险库
+fn vault_lock_action() -> Result<()> {
+    lock_vault()?;
+
+    println!("\n[Aspen Success] ==> {}\n", "保险库已锁定".green());
+    Ok(())
+}
+
+// 设置解锁缓存时长
+fn vault_timeout_action(matches: &ArgMatches) -> Result<()> {
+    let seconds: u64 = matches
+        .get_one::<String>("seconds")
+        .unwrap()
+        .trim()
+        .parse()
+        .map_err(|_| AspenError::Usage("缓存时长只能输入合法的整数(秒)!".to_string()))?;
+
+    let mut aspen_config = get_aspen_config()?;
+    aspen_config.vault_cache_seconds = seconds;
+    write_aspen_config(&aspen_config)?;
+
+    // 缩短缓存时长时,已缓存的密钥一并失效
+    lock_vault()?;
+    println!("\n[Aspen Success] ==> 保险库缓存时长已设置为 {} 秒\n", seconds.to_string().green());
+    Ok(())
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use colored::Colorize;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::aspen_module::cli::{generate_folder, get_home_dir};
+use crate::aspen_module::config::get_aspen_config;
+use crate::aspen_module::error::{AspenError, Result};
+
+// 服务器配置中引用保险库条目的前缀,如 "vault:prod-root"
+pub const VAULT_PREFIX: &str = "vault:";
+
+const VAULT_VERSION: u32 = 1;
+
+// 落盘的保险库文件,条目整体加密
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+// 解锁后缓存的密钥,以运行时目录中的随机密钥加密,单独复制该文件无法解锁保险库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultSession {
+    salt: String,
+    nonce: String,
+    key: String,
+    expires_at: u64,
+}
+
+pub struct Vault {
+    salt: Vec<u8>,
+    key: [u8; 32],
+    pub entries: BTreeMap<String, String>,
+}
+
+impl Vault {
+    /**
+     * 加密并写回保险库文件
+     */
+    pub fn save(&self) -> Result<()> {
+        let file = encrypt_entries(&self.entries, &self.salt, &self.key)?;
+        let content = serde_json::to_string_pretty(&file)
+            .map_err(|_| AspenError::Vault("保险库加密失败!".to_string()))?;
+        write_private_file(&vault_path()?, content.as_bytes())
+    }
+}
+
+/**
+ * 保险库是否已创建
+ */
+pub fn vault_exists() -> Result<bool> {
+    Ok(vault_path()?.exists())
+}
+
+/**
+ * 解锁保险库,缓存未过期时不再询问主密码
+ */
+pub fn unlock_vault() -> Result<Vault> {
+    if !vault_exists()? {
+        return Err(AspenError::Vault("保险库尚未创建,请先执行命令 vault set 添加条目!".to_string()));
+    }
+
+    let file = read_vault_file()?;
+    let salt = decode_base64(&file.salt)?;
+
+    if let Some(key) = cached_key(&file.salt) {
+        if let Some(entries) = decrypt_entries(&file, &key) {
+            return Ok(Vault { salt, key, entries });
+        }
+    }
+
+    let password = prompt_secret("请输入保险库主密码: ")?;
+    let key = derive_key(&password, &salt)?;
+
+    match decrypt_entries(&file, &key) {
+        Some(entries) => {
+            cache_key(&file.salt, &key)?;
+            Ok(Vault { salt, key, entries })
+        }
+        None => Err(AspenError::Vault("保险库主密码错误!".to_string())),
+    }
+}
+
+/**
+ * 打开保险库,不存在时引导设置主密码并创建
+ */
+pub fn open_or_create_vault() -> Result<Vault> {
+    if vault_exists()? {
+        return unlock_vault();
+    }
+
+    println!("\n[Aspen Waiting] ==> {}", "保险库尚未创建,请设置主密码".green());
+    let password = prompt_secret("请输入新的主密码: ")?;
+    if password.is_empty() {
+        return Err(AspenError::Usage("主密码不能为空!".to_string()));
+    }
+
+    let confirm = prompt_secret("请再次输入主密码: ")?;
+    if password != confirm {
+        return Err(AspenError::Usage("两次输入的主密码不一致!".to_string()));
+    }
+
+    let mut salt = vec![0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(&password, &salt)?;
+    cache_key(&STANDARD.encode(&salt), &key)?;
+
+    Ok(Vault { salt, key, entries: BTreeMap::new() })
+}
+
+/**
+ * 清除缓存的密钥,下次使用需重新输入主密码
+ */
+pub fn lock_vault() -> Result<()> {
+    let mut paths = vec![session_path()?];
+    paths.extend(wrapping_key_path());
+
+    for path in paths {
+        if path.exists() && fs::remove_file(&path).is_err() {
+            return Err(AspenError::Io("清除保险库缓存失败!".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * 解析 "vault:<key>" 形式的引用,普通值原样返回
+ */
+pub fn resolve_secret(vault: &mut Option<Vault>, value: &str) -> Result<String> {
+    let key = match value.strip_prefix(VAULT_PREFIX) {
+        Some(key) => key,
+        None => return Ok(value.to_string()),
+    };
+
+    if vault.is_none() {
+        *vault = Some(unlock_vault()?);
+    }
+    match vault.as_ref().and_then(|vault| vault.entries.get(key)) {
+        Some(secret) => Ok(secret.clone()),
+        None => Err(AspenError::Vault(format!("保险库中不存在条目 {}!", key))),
+    }
+}
+
+/**
+ * 提示并读取不回显的输入
+ */
+pub fn prompt_secret(prompt: &str) -> Result<String> {
+    // 非终端输入(管道、脚本)时按行读取
+    if !io::stdin().is_terminal() {
+        eprint!("{}", prompt);
+        let mut value = String::new();
+        io::stdin().read_line(&mut value).map_err(|_| AspenError::Io("读取输入失败!".to_string()))?;
+        return Ok(value.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    rpassword::prompt_password(prompt).map_err(|_| AspenError::Io("读取输入失败!".to_string()))
+}
+
+fn vault_dir() -> Result<PathBuf> {
+    let dir = env!("CARGO_PKG_NAME");
+    let vault_dir = format!("{}/{}/vault", get_home_dir()?.to_str().unwrap(), dir);
+    generate_folder(vault_dir.clone())?;
+
+    Ok(PathBuf::from(vault_dir))
+}
+
+fn vault_path() -> Result<PathBuf> {
+    Ok(vault_dir()?.join("vault.json"))
+}
+
+fn session_path() -> Result<PathBuf> {
+    Ok(vault_dir()?.join("session.json"))
+}
+
+// 加密缓存密钥所用的随机密钥放在当前用户独占的运行时目录(linux 为 tmpfs,注销或重启后清空)
+// 没有这样的目录时不缓存
+fn wrapping_key_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        // macOS、Windows 的临时目录为当前用户独占
+        _ if cfg!(any(target_os = "macos", target_os = "windows")) => env::temp_dir(),
+        _ => return None,
+    };
+
+    Some(dir.join(format!("{}-vault-session.key", env!("CARGO_PKG_NAME"))))
+}
+
+fn read_vault_file() -> Result<VaultFile> {
+    let contents = fs::read_to_string(vault_path()?)
+        .map_err(|_| AspenError::Io("读取保险库文件失败!".to_string()))?;
+
+    match serde_json::from_str::<VaultFile>(&contents) {
+        Ok(file) if file.version == VAULT_VERSION => Ok(file),
+        _ => Err(AspenError::Vault("保险库文件已损坏或版本不受支持!".to_string())),
+    }
+}
+
+fn encrypt_entries(entries: &BTreeMap<String, String>, salt: &[u8], key: &[u8; 32]) -> Result<VaultFile> {
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+
+    let plaintext = serde_json::to_vec(entries)
+        .map_err(|_| AspenError::Vault("保险库加密失败!".to_string()))?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| AspenError::Vault("保险库加密失败!".to_string()))?;
+
+    Ok(VaultFile {
+        version: VAULT_VERSION,
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+fn decrypt_entries(file: &VaultFile, key: &[u8; 32]) -> Option<BTreeMap<String, String>> {
+    let nonce = STANDARD.decode(&file.nonce).ok()?;
+    let ciphertext = STANDARD.decode(&file.ciphertext).ok()?;
+    if nonce.len() != 12 {
+        return None;
+    }
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let plaintext = cipher.decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice()).ok()?;
+
+    serde_json::from_slice(&plaintext).ok()
+}
+
+// 使用 Argon2id 从主密码派生加密密钥
+fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    if Argon2::default().hash_password_into(password.as_bytes(), salt, &mut key).is_err() {
+        return Err(AspenError::Vault("保险库密钥派生失败!".to_string()));
+    }
+
+    Ok(key)
+}
+
+fn cached_key(salt: &str) -> Option<[u8; 32]> {
+    let path = session_path().ok()?;
+    let contents = fs::read_to_string(&path).ok()?;
+
+    let wrapping_key = read_wrapping_key();
+    let key = serde_json::from_str::<VaultSession>(&contents)
+        .ok()
+        .zip(wrapping_key)
+        .and_then(|(session, wrapping_key)| session_key(&session, salt, &wrapping_key, now_seconds()));
+
+    // 已过期、不属于当前保险库或无法解密的缓存直接删除
+    if key.is_none() {
+        let _ = fs::remove_file(&path);
+    }
+
+    key
+}
+
+fn cache_key(salt: &str, key: &[u8; 32]) -> Result<()> {
+    let seconds = get_aspen_config()?.vault_cache_seconds;
+    let wrapping_key = match wrapping_key_path() {
+        Some(path) if seconds > 0 => read_or_create_wrapping_key(&path)?,
+        _ => return Ok(()),
+    };
+
+    let session = wrap_key(salt, key, &wrapping_key, now_seconds().saturating_add(seconds))?;
+    let content = serde_json::to_string(&session)
+        .map_err(|_| AspenError::Vault("保险库缓存写入失败!".to_string()))?;
+    write_private_file(&session_path()?, content.as_bytes())
+}
+
+// 以随机密钥加密保险库密钥,过期时间作为附加数据参与认证,修改后无法解密
+fn wrap_key(salt: &str, key: &[u8; 32], wrapping_key: &[u8; 32], expires_at: u64) -> Result<VaultSession> {
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+
+    let aad = session_aad(salt, expires_at);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(wrapping_key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: key, aad: aad.as_bytes() })
+        .map_err(|_| AspenError::Vault("保险库缓存写入失败!".to_string()))?;
+
+    Ok(VaultSession {
+        salt: salt.to_string(),
+        nonce: STANDARD.encode(nonce),
+        key: STANDARD.encode(ciphertext),
+        expires_at,
+    })
+}
+
+// 缓存属于当前保险库(salt 一致)且未过期时解出保险库密钥
+fn session_key(session: &VaultSession, salt: &str, wrapping_key: &[u8; 32], now: u64) -> Option<[u8; 32]> {
+    if session.salt != salt || session.expires_at <= now {
+        return None;
+    }
+
+    let nonce = STANDARD.decode(&session.nonce).ok()?;
+    let ciphertext = STANDARD.decode(&session.key).ok()?;
+    if nonce.len() != 12 {
+        return None;
+    }
+
+    let aad = session_aad(&session.salt, session.expires_at);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(wrapping_key));
+    let key = cipher.decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: aad.as_bytes() }).ok()?;
+
+    key.try_into().ok()
+}
+
+fn read_wrapping_key() -> Option<[u8; 32]> {
+    STANDARD.decode(fs::read_to_string(wrapping_key_path()?).ok()?.trim()).ok()?.try_into().ok()
+}
+
+fn read_or_create_wrapping_key(path: &PathBuf) -> Result<[u8; 32]> {
+    let existing = fs::read_to_string(path).ok().and_then(|contents| STANDARD.decode(contents.trim()).ok());
+    if let Some(Ok(key)) = existing.map(<[u8; 32]>::try_from) {
+        return Ok(key);
+    }
+
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    write_private_file(path, STANDARD.encode(key).as_bytes())?;
+
+    Ok(key)
+}
+
+fn session_aad(salt: &str, expires_at: u64) -> String {
+    format!("{}:{}", salt, expires_at)
+}
+
+fn now_seconds() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// 先写临时文件再重命名,unix 下权限为 600
+fn write_private_file(path: &PathBuf, content: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    options.open(&tmp_path)
+        .and_then(|mut file| file.write_all(content).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|_| AspenError::Io(format!("写入文件 {} 失败!", path.display())))
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>> {
+    STANDARD.decode(value).map_err(|_| AspenError::Vault("保险库文件已损坏!".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT: &[u8] = b"0123456789abcdef";
+
+    fn entries() -> BTreeMap<String, String> {
+        BTreeMap::from([("prod-root".to_string(), "s3cret".to_string())])
+    }
+
+    #[test]
+    fn entries_round_trip_with_the_derived_key() {
+        let key = derive_key("master", SALT).unwrap();
+        let file = encrypt_entries(&entries(), SALT, &key).unwrap();
+
+        assert_eq!(file.salt, STANDARD.encode(SALT));
+        assert!(!file.ciphertext.contains("s3cret"));
+        assert_eq!(decrypt_entries(&file, &key), Some(entries()));
+        // 同一内容每次加密使用新的 nonce
+        assert_ne!(encrypt_entries(&entries(), SALT, &key).unwrap().nonce, file.nonce);
+    }
+
+    #[test]
+    fn wrong_master_password_fails_to_decrypt() {
+        let key = derive_key("master", SALT).unwrap();
+        let file = encrypt_entries(&entries(), SALT, &key).unwrap();
+
+        assert_eq!(decrypt_entries(&file, &derive_key("Master", SALT).unwrap()), None);
+        assert_eq!(decrypt_entries(&file, &derive_key("master", b"fedcba9876543210").unwrap()), None);
+    }
+
+    #[test]
+    fn corrupted_ciphertext_or_nonce_fails_to_decrypt() {
+        let key = [7u8; 32];
+        let file = encrypt_entries(&entries(), SALT, &key).unwrap();
+
+        let mut ciphertext = STANDARD.decode(&file.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        let tampered = VaultFile { ciphertext: STANDARD.encode(ciphertext), ..file.clone() };
+        assert_eq!(decrypt_entries(&tampered, &key), None);
+
+        let other_nonce = VaultFile { nonce: STANDARD.encode([0u8; 12]), ..file.clone() };
+        assert_eq!(decrypt_entries(&other_nonce, &key), None);
+
+        let short_nonce = VaultFile { nonce: STANDARD.encode([0u8; 8]), ..file.clone() };
+        assert_eq!(decrypt_entries(&short_nonce, &key), None);
+
+        let not_base64 = VaultFile { ciphertext: "not base64!".to_string(), ..file.clone() };
+        assert_eq!(decrypt_entries(&not_base64, &key), None);
+    }
+
+    #[test]
+    fn cached_key_expires() {
+        let (key, wrapping_key) = ([1u8; 32], [2u8; 32]);
+        let session = wrap_key("salt", &key, &wrapping_key, 100).unwrap();
+
+        assert_eq!(session_key(&session, "salt", &wrapping_key, 99), Some(key));
+        assert_eq!(session_key(&session, "salt", &wrapping_key, 100), None);
+        assert_eq!(session_key(&session, "salt", &wrapping_key, 200), None);
+
+        // 改写过期时间后认证失败
+        let extended = VaultSession { expires_at: 1000, ..session.clone() };
+        assert_eq!(session_key(&extended, "salt", &wrapping_key, 200), None);
+    }
+
+    #[test]
+    fn cached_key_requires_matching_salt_and_wrapping_key() {
+        let (key, wrapping_key) = ([1u8; 32], [2u8; 32]);
+        let session = wrap_key("salt", &key, &wrapping_key, 100).unwrap();
+
+        assert_eq!(session_key(&session, "other-salt", &wrapping_key, 50), None);
+        assert_eq!(session_key(&session, "salt", &[3u8; 32], 50), None);
+
+        // 改写 salt 以冒充其他保险库同样无法解密
+        let moved = VaultSession { salt: "other-salt".to_string(), ..session.clone() };
+        assert_eq!(session_key(&moved, "other-salt", &wrapping_key, 50), None);
+    }
+}